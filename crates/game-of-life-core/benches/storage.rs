@@ -0,0 +1,24 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use game_of_life_core::{prelude::*, state::Storage};
+
+fn step(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("step");
+    group.sample_size(10);
+
+    for size in [256, 1024, 4096] {
+        for storage in [Storage::Bytes, Storage::Packed] {
+            let mut state = State::from_seed(size, size, 0, 0.5).with_storage(storage);
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("{storage:?}"), format!("{size}x{size}")),
+                &size,
+                |bencher, _| bencher.iter(|| state.step()),
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, step);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+use crate::state::{CellState, State};
+
+#[cfg(test)]
+mod tests;
+
+/// How many generations each cell of a board has been alive for, such as to color cells by their age.
+///
+/// The ages are kept up to date by passing the state to [`Ages::update`] after every step or edit. Cells which are
+/// dying or dead have an age of 0, and a cell which has just been born has an age of 1.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Ages {
+    ages: Vec<u32>,
+    generation: u64,
+}
+
+impl Ages {
+    /// Starts tracking the ages of a board, where every alive cell is treated as just born.
+    #[must_use]
+    pub fn new(state: &State) -> Self {
+        Self {
+            ages: (0..state.width() * state.height())
+                .map(|index| u32::from(state.at_index(index) == Some(CellState::Alive)))
+                .collect(),
+            generation: state.generation(),
+        }
+    }
+
+    /// Gets the age of the cell at an index, or 0 for cells beyond the board.
+    #[must_use]
+    pub fn get(&self, index: usize) -> u32 {
+        self.ages.get(index).copied().unwrap_or(0)
+    }
+
+    /// Updates the ages to match the state, returning the indices of the cells whose age changed.
+    ///
+    /// Every alive cell ages when the state has stepped forward a single generation. Otherwise, such as after an
+    /// edit, stepping back, or resizing the board, only births and deaths change the ages.
+    pub fn update(&mut self, state: &State) -> Vec<usize> {
+        let area = state.width() * state.height();
+        if area != self.ages.len() {
+            *self = Self::new(state);
+            return (0..area).collect();
+        }
+
+        let has_stepped = state.generation() == self.generation + 1;
+        self.generation = state.generation();
+
+        // Cells are looked at one by one, as copying the whole board every frame is slow for large boards
+        self.ages
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, age)| {
+                let new_age = match state.at_index(index) {
+                    Some(CellState::Alive) if has_stepped => age.saturating_add(1),
+                    Some(CellState::Alive) => (*age).max(1),
+                    Some(CellState::Dying(_) | CellState::Dead) | None => 0,
+                };
+
+                (std::mem::replace(age, new_age) != new_age).then_some(index)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,72 @@
+use super::*;
+use crate::{boundary::Boundary, rule::Rule};
+
+fn block_and_blinker() -> State {
+    let cells = (0..50)
+        .map(|index| CellState::from([11, 12, 21, 22, 17, 27, 37].contains(&index)))
+        .collect();
+
+    State::from_cells(10, 5, cells)
+        .with_boundary(Boundary::Dead)
+        .with_history(1 << 10)
+}
+
+#[test]
+fn alive_cells_age_each_generation() {
+    let mut state = block_and_blinker();
+    let mut ages = Ages::new(&state);
+    assert_eq!(ages.get(11), 1);
+    assert_eq!(ages.get(0), 0);
+
+    for _ in 0..3 {
+        state.step();
+        ages.update(&state);
+    }
+
+    // The block never changes, while the middle of the blinker is the only cell which stays alive
+    assert_eq!(ages.get(11), 4);
+    assert_eq!(ages.get(27), 4);
+    assert_eq!(ages.get(17), 0);
+    assert_eq!(ages.get(26), 1);
+}
+
+#[test]
+fn updates_return_the_cells_which_changed() {
+    let mut state = block_and_blinker();
+    let mut ages = Ages::new(&state);
+
+    state.step();
+    let changed = ages.update(&state);
+    assert_eq!(changed, [11, 12, 17, 21, 22, 26, 27, 28, 37]);
+
+    // Edits are not a generation, so only the edited cell changes
+    state.replace_at_index(0, CellState::Alive);
+    assert_eq!(ages.update(&state), [0]);
+    assert_eq!(ages.get(11), 2);
+}
+
+#[test]
+fn stepping_back_keeps_ages() {
+    let mut state = block_and_blinker();
+    let mut ages = Ages::new(&state);
+    state.step();
+    ages.update(&state);
+
+    state.step_back();
+    ages.update(&state);
+    assert_eq!(ages.get(11), 2);
+    assert_eq!(ages.get(17), 1);
+    assert_eq!(ages.get(26), 0);
+}
+
+#[test]
+fn dying_cells_have_no_age() {
+    let mut state = State::empty(3, 3).with_rule("B2/S/C3".parse::<Rule>().unwrap());
+    state.replace_at_index(4, CellState::Alive);
+    let mut ages = Ages::new(&state);
+
+    state.step();
+    ages.update(&state);
+    assert_eq!(state.at_index(4), Some(CellState::Dying(1)));
+    assert_eq!(ages.get(4), 0);
+}
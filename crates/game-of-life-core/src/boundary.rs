@@ -0,0 +1,114 @@
+use crate::Coordinates;
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+/// How cells beyond the edges of the board are treated when counting neighbours.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub enum Boundary {
+    /// The board wraps around at every edge, so gliders reappear on the other side.
+    #[default]
+    Toroidal,
+    /// Every cell beyond the edge is dead.
+    Dead,
+    /// The board is mirrored at its edges, so the cells beyond an edge copy the cells just inside it.
+    Reflective,
+    /// The board wraps around at every edge, but is flipped horizontally when wrapping vertically.
+    KleinBottle,
+}
+
+impl Boundary {
+    /// Finds the cell on the board that the given coordinates refer to, or `None` when they refer to a dead cell
+    /// beyond the edge.
+    #[must_use]
+    pub fn resolve(self, coordinates: Coordinates, width: i32, height: i32) -> Option<Coordinates> {
+        let Coordinates { x, y } = coordinates;
+
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            return Some(coordinates);
+        }
+
+        match self {
+            Self::Toroidal => Some(Coordinates {
+                x: x.rem_euclid(width),
+                y: y.rem_euclid(height),
+            }),
+            Self::Dead => None,
+            Self::Reflective => {
+                let reflect = |position: i32, length: i32| {
+                    let position = position.rem_euclid(length * 2);
+                    if position < length {
+                        position
+                    } else {
+                        length * 2 - 1 - position
+                    }
+                };
+
+                Some(Coordinates {
+                    x: reflect(x, width),
+                    y: reflect(y, height),
+                })
+            }
+            Self::KleinBottle => {
+                let x = x.rem_euclid(width);
+
+                Some(Coordinates {
+                    // Each time the top or bottom edge is crossed, the board is flipped
+                    x: if y.div_euclid(height) % 2 == 0 {
+                        x
+                    } else {
+                        width - 1 - x
+                    },
+                    y: y.rem_euclid(height),
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for Boundary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Toroidal => "torus",
+            Self::Dead => "dead",
+            Self::Reflective => "mirror",
+            Self::KleinBottle => "klein",
+        })
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown boundary {0:?}, expected one of torus, dead, mirror or klein")]
+pub struct ParseBoundaryError(String);
+
+impl FromStr for Boundary {
+    type Err = ParseBoundaryError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "torus" => Ok(Self::Toroidal),
+            "dead" => Ok(Self::Dead),
+            "mirror" => Ok(Self::Reflective),
+            "klein" => Ok(Self::KleinBottle),
+            _ => Err(ParseBoundaryError(input.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for Boundary {
+    type Error = ParseBoundaryError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+impl From<Boundary> for String {
+    fn from(boundary: Boundary) -> Self {
+        boundary.to_string()
+    }
+}
@@ -0,0 +1,553 @@
+use crate::{
+    boundary::Boundary,
+    neighbourhood,
+    rule::Rule,
+    state::{CellState, State, Storage},
+    Coordinates,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// The most generations an object is stepped while waiting for it to repeat.
+const MAX_PERIOD: u64 = 64;
+
+/// The widest or tallest an object can be in any phase for it to be given a full apgcode.
+const MAX_SIZE: usize = 40;
+
+/// Groups of touching cells which don't repeat on their own are joined with any group within this many cells, as they
+/// may be parts of one object whose cells don't all touch, such as a spaceship.
+const OBJECT_SPACING: i32 = 2;
+
+const APGCODE_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// The names of some of the most common objects under Conway's game of life.
+const NAMES: [(&str, &str); 15] = [
+    ("xs4_33", "block"),
+    ("xs6_696", "beehive"),
+    ("xs7_2596", "loaf"),
+    ("xs5_253", "boat"),
+    ("xs4_252", "tub"),
+    ("xs8_6996", "pond"),
+    ("xs6_356", "ship"),
+    ("xs6_25a4", "barge"),
+    ("xp2_7", "blinker"),
+    ("xp2_7e", "toad"),
+    ("xp2_318c", "beacon"),
+    ("xq4_153", "glider"),
+    ("xq4_6frc", "lightweight spaceship"),
+    ("xq4_27dee6", "middleweight spaceship"),
+    ("xq4_27deee6", "heavyweight spaceship"),
+];
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum UnsupportedRuleError {
+    #[error("objects can't be identified under {0}, as empty space would not stay empty")]
+    BirthFromNothing(Rule),
+    #[error("objects can't be identified under {0}, as apgcodes only describe cells which are alive or dead")]
+    TooManyStates(Rule),
+    #[error("objects can't be identified with the {0} neighbourhood, as apgcodes only describe the eight surrounding cells")]
+    Neighbourhood(String),
+}
+
+/// A group of alive cells found on a board, identified by its apgcode.
+///
+/// Apgcodes start with `xs` and the population for still lifes, `xp` and the period for oscillators, or `xq` and
+/// the period for spaceships, followed by the cells of the object's smallest phase and orientation. Objects too large
+/// to describe start with `ov_` instead, and objects which don't repeat are `zz_APERIODIC`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Object {
+    pub apgcode: String,
+    /// The alive cells of the object, where cells which wrapped around the edges of the board are beyond the edges.
+    pub cells: Vec<Coordinates>,
+}
+
+impl Object {
+    /// Gets the common name of the object, if it has one.
+    #[must_use]
+    pub fn name(&self) -> Option<&'static str> {
+        name(&self.apgcode)
+    }
+}
+
+/// Gets the common name of the object with an apgcode, if it has one.
+#[must_use]
+pub fn name(apgcode: &str) -> Option<&'static str> {
+    NAMES
+        .iter()
+        .find(|&&(code, _)| code == apgcode)
+        .map(|&(_, name)| name)
+}
+
+/// The objects on a board.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Census {
+    objects: Vec<Object>,
+}
+
+impl Census {
+    /// Splits the alive cells of a board into objects, and identifies each by stepping it on its own until it repeats.
+    ///
+    /// Objects close enough to affect each other are found as one object, so this is most useful once the board has
+    /// settled.
+    ///
+    /// # Errors
+    /// When the rule causes cells with no alive neighbours to be born, or has dying states, or when the neighbourhood
+    /// is not the eight surrounding cells.
+    pub fn take(state: &State) -> Result<Self, UnsupportedRuleError> {
+        let rule = state.rule();
+        if rule.is_born(0) {
+            return Err(UnsupportedRuleError::BirthFromNothing(rule));
+        }
+        if rule.states() > 2 {
+            return Err(UnsupportedRuleError::TooManyStates(rule));
+        }
+        if !neighbourhood::is_moore(state.neighbourhood()) {
+            return Err(UnsupportedRuleError::Neighbourhood(
+                state.neighbourhood().to_string(),
+            ));
+        }
+
+        // Objects with the same cells are only stepped once
+        let mut apgcodes = HashMap::new();
+        let identify = |cells: &[Coordinates]| {
+            let (shape, _) = normalise(cells);
+            apgcodes
+                .entry(shape)
+                .or_insert_with_key(|shape| identify(shape, rule))
+                .clone()
+        };
+
+        let objects = segment(&AliveCells::of(state), identify)
+            .into_iter()
+            .map(|(cells, apgcode)| Object {
+                apgcode: apgcode.unwrap_or_else(|| String::from("zz_APERIODIC")),
+                cells,
+            })
+            .collect();
+
+        Ok(Self { objects })
+    }
+
+    #[must_use]
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// Counts the objects with each apgcode, from the most to the least common.
+    #[must_use]
+    pub fn counts(&self) -> Vec<(&str, usize)> {
+        let mut counts = HashMap::<&str, usize>::new();
+        for object in &self.objects {
+            *counts.entry(&object.apgcode).or_default() += 1;
+        }
+
+        let mut counts = counts.into_iter().collect::<Vec<_>>();
+        counts.sort_unstable_by(|(apgcode, count), (other_apgcode, other_count)| {
+            other_count.cmp(count).then(apgcode.cmp(other_apgcode))
+        });
+        counts
+    }
+
+    /// Counts the objects with an apgcode.
+    #[must_use]
+    pub fn count(&self, apgcode: &str) -> usize {
+        self.objects
+            .iter()
+            .filter(|object| object.apgcode == apgcode)
+            .count()
+    }
+}
+
+impl fmt::Display for Census {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (apgcode, count)) in self.counts().into_iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+
+            match name(apgcode) {
+                Some(name) => write!(f, "{count} {name} ({apgcode})")?,
+                None => write!(f, "{count} {apgcode}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The alive cells of a board, along with the size of the board when cells beyond its edges wrap around.
+struct AliveCells {
+    /// The alive cells in row order, so groups are always found starting from their first cell
+    cells: Vec<Coordinates>,
+    torus: Option<(i32, i32)>,
+}
+
+impl AliveCells {
+    fn of(state: &State) -> Self {
+        if state.storage() == Storage::Unbounded {
+            // Alive cells of an unbounded board can be beyond the viewport
+            let Some(bounding_box) = state.bounding_box() else {
+                return Self {
+                    cells: Vec::new(),
+                    torus: None,
+                };
+            };
+
+            let viewport = state.viewport();
+            let cells = state.window(
+                Coordinates {
+                    y: viewport.y + i64::from(bounding_box.top_left.y),
+                    x: viewport.x + i64::from(bounding_box.top_left.x),
+                },
+                bounding_box.width,
+                bounding_box.height,
+            );
+
+            return Self {
+                cells: alive_coordinates(&cells, bounding_box.width)
+                    .map(|Coordinates { y, x }| Coordinates {
+                        y: y + bounding_box.top_left.y,
+                        x: x + bounding_box.top_left.x,
+                    })
+                    .collect(),
+                torus: None,
+            };
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let torus = (state.boundary() == Boundary::Toroidal)
+            .then_some((state.width() as i32, state.height() as i32));
+
+        Self {
+            cells: alive_coordinates(&state.cells(), state.width()).collect(),
+            torus,
+        }
+    }
+
+    /// Finds where coordinates beyond the edges of a torus wrap around to.
+    fn wrap(&self, Coordinates { y, x }: Coordinates) -> Coordinates {
+        match self.torus {
+            Some((width, height)) => Coordinates {
+                y: y.rem_euclid(height),
+                x: x.rem_euclid(width),
+            },
+            None => Coordinates { y, x },
+        }
+    }
+}
+
+/// Finds the offsets to every cell within a distance of a cell, including the cell itself.
+fn offsets_within(distance: i32) -> impl Iterator<Item = Coordinates> {
+    (-distance..=distance)
+        .flat_map(move |y| (-distance..=distance).map(move |x| Coordinates { y, x }))
+}
+
+/// Splits the alive cells of a board into objects, identifying each with `identify`, which is `None` when the cells
+/// don't repeat on their own.
+///
+/// Touching cells are grouped first, so objects which are close but don't affect each other are kept apart. Groups
+/// are followed around the edges of a torus, so each cell of a group is placed beside its neighbours.
+fn segment(
+    alive: &AliveCells,
+    mut identify: impl FnMut(&[Coordinates]) -> Option<String>,
+) -> Vec<(Vec<Coordinates>, Option<String>)> {
+    let mut remaining = alive.cells.iter().copied().collect::<HashSet<_>>();
+    let mut groups = Vec::new();
+
+    for &start in &alive.cells {
+        if !remaining.remove(&start) {
+            continue;
+        }
+
+        let mut group = Vec::new();
+        let mut unvisited = vec![start];
+
+        while let Some(coordinates) = unvisited.pop() {
+            group.push(coordinates);
+
+            for offset in offsets_within(1) {
+                let neighbour = Coordinates {
+                    y: coordinates.y + offset.y,
+                    x: coordinates.x + offset.x,
+                };
+                if remaining.remove(&alive.wrap(neighbour)) {
+                    unvisited.push(neighbour);
+                }
+            }
+        }
+
+        let apgcode = identify(&group);
+        groups.push((group, apgcode));
+    }
+
+    // Groups which don't repeat are joined with a nearby group until every group repeats, or none are nearby
+    loop {
+        let owners = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(index, (cells, _))| {
+                cells
+                    .iter()
+                    .map(move |&cell| (alive.wrap(cell), (index, cell)))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let nearby = groups
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, apgcode))| apgcode.is_none())
+            .find_map(|(index, (cells, _))| {
+                cells.iter().find_map(|cell| {
+                    offsets_within(OBJECT_SPACING).find_map(|offset| {
+                        let neighbour = Coordinates {
+                            y: cell.y + offset.y,
+                            x: cell.x + offset.x,
+                        };
+                        let &(owner, owner_cell) = owners.get(&alive.wrap(neighbour))?;
+
+                        // The other group is moved to be beside this one, in case it was found across an edge
+                        (owner != index).then_some((
+                            index,
+                            owner,
+                            Coordinates {
+                                y: neighbour.y - owner_cell.y,
+                                x: neighbour.x - owner_cell.x,
+                            },
+                        ))
+                    })
+                })
+            });
+
+        let Some((index, owner, shift)) = nearby else {
+            break;
+        };
+
+        let (owner_cells, _) = groups.swap_remove(owner);
+        let index = if index == groups.len() { owner } else { index };
+
+        let cells = &mut groups[index].0;
+        cells.extend(
+            owner_cells
+                .into_iter()
+                .map(|Coordinates { y, x }| Coordinates {
+                    y: y + shift.y,
+                    x: x + shift.x,
+                }),
+        );
+        groups[index].1 = identify(cells);
+    }
+
+    for (cells, _) in &mut groups {
+        cells.sort_unstable_by_key(|&Coordinates { y, x }| (y, x));
+    }
+    groups.sort_unstable_by_key(|(cells, _)| cells.first().map(|&Coordinates { y, x }| (y, x)));
+
+    groups
+}
+
+fn alive_coordinates(cells: &[CellState], width: usize) -> impl Iterator<Item = Coordinates> + '_ {
+    cells
+        .iter()
+        .enumerate()
+        .filter(|&(_, &state)| state == CellState::Alive)
+        .map(
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            move |(index, _)| Coordinates {
+                y: (index / width) as i32,
+                x: (index % width) as i32,
+            },
+        )
+}
+
+/// Moves cells so the smallest rectangle holding them starts at the origin, returning the sorted cells and where the
+/// rectangle was.
+fn normalise(cells: &[Coordinates]) -> (Vec<Coordinates>, Coordinates) {
+    let top_left = Coordinates {
+        y: cells.iter().map(|cell| cell.y).min().unwrap_or(0),
+        x: cells.iter().map(|cell| cell.x).min().unwrap_or(0),
+    };
+
+    let mut cells = cells
+        .iter()
+        .map(|&Coordinates { y, x }| Coordinates {
+            y: y - top_left.y,
+            x: x - top_left.x,
+        })
+        .collect::<Vec<_>>();
+    cells.sort_unstable_by_key(|&Coordinates { y, x }| (y, x));
+
+    (cells, top_left)
+}
+
+/// Gets the width and height of normalised cells.
+fn size(cells: &[Coordinates]) -> (usize, usize) {
+    let extent =
+        |length: Option<i32>| length.map_or(0, |length| length.unsigned_abs() as usize + 1);
+
+    (
+        extent(cells.iter().map(|cell| cell.x).max()),
+        extent(cells.iter().map(|cell| cell.y).max()),
+    )
+}
+
+/// Finds the apgcode of an object from its normalised cells by stepping it on its own, or `None` when it doesn't
+/// repeat.
+fn identify(cells: &[Coordinates], rule: Rule) -> Option<String> {
+    let (width, height) = size(cells);
+
+    // The object can't reach the edges before it is given up on, as nothing moves faster than one cell a generation
+    #[allow(clippy::cast_possible_truncation)]
+    let margin = MAX_PERIOD as usize + 1;
+    let board_width = width + 2 * margin;
+
+    let mut state = State::empty(board_width, height + 2 * margin)
+        .with_rule(rule)
+        .with_boundary(Boundary::Dead);
+
+    #[allow(clippy::cast_sign_loss)]
+    for &Coordinates { y, x } in cells {
+        state.replace_at_index(
+            (y as usize + margin) * board_width + x as usize + margin,
+            CellState::Alive,
+        );
+    }
+
+    let mut smallest_phase = None::<String>;
+    let mut is_oversized = false;
+
+    for period in 1..=MAX_PERIOD {
+        let (phase, _) =
+            normalise(&alive_coordinates(&state.cells(), board_width).collect::<Vec<_>>());
+        let (phase_width, phase_height) = size(&phase);
+
+        if phase_width > MAX_SIZE || phase_height > MAX_SIZE {
+            is_oversized = true;
+        } else {
+            // The smallest description is the shortest, and then the first in alphabetical order
+            smallest_phase = orientations(&phase)
+                .map(|orientation| wechsler(&orientation))
+                .chain(smallest_phase.take())
+                .min_by(|first, second| first.len().cmp(&second.len()).then(first.cmp(second)));
+        }
+
+        state.step();
+
+        let (next, top_left) =
+            normalise(&alive_coordinates(&state.cells(), board_width).collect::<Vec<_>>());
+        if next.is_empty() {
+            break;
+        }
+
+        if next == cells {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let has_moved = top_left
+                != Coordinates {
+                    y: margin as i32,
+                    x: margin as i32,
+                };
+
+            let kind = match (has_moved, period) {
+                (true, _) => format!("q{period}"),
+                (false, 1) => format!("s{}", cells.len()),
+                (false, _) => format!("p{period}"),
+            };
+
+            return Some(if is_oversized {
+                format!("ov_{kind}")
+            } else {
+                format!("x{kind}_{}", smallest_phase.unwrap_or_default())
+            });
+        }
+    }
+
+    None
+}
+
+/// Finds each of the eight rotations and reflections of normalised cells.
+fn orientations(cells: &[Coordinates]) -> impl Iterator<Item = Vec<Coordinates>> + '_ {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let (last_x, last_y) = {
+        let (width, height) = size(cells);
+        (width as i32 - 1, height as i32 - 1)
+    };
+
+    (0..8).map(move |orientation| {
+        let mut cells = cells
+            .iter()
+            .map(|&Coordinates { y, x }| {
+                let y = if orientation & 1 == 0 { y } else { last_y - y };
+                let x = if orientation & 2 == 0 { x } else { last_x - x };
+
+                if orientation & 4 == 0 {
+                    Coordinates { y, x }
+                } else {
+                    Coordinates { y: x, x: y }
+                }
+            })
+            .collect::<Vec<_>>();
+        cells.sort_unstable_by_key(|&Coordinates { y, x }| (y, x));
+        cells
+    })
+}
+
+/// Writes normalised cells in extended Wechsler format, where each column of each strip of five rows is a digit, with
+/// the top row as the lowest bit, and strips are separated by `z`.
+///
+/// Runs of empty columns are shortened to `w` for two, `x` for three, or `y` and a digit for four or more, and empty
+/// columns at the end of a strip are left out.
+fn wechsler(cells: &[Coordinates]) -> String {
+    let (width, height) = size(cells);
+    let cells = cells
+        .iter()
+        .map(|&Coordinates { y, x }| (y, x))
+        .collect::<HashSet<_>>();
+
+    let mut code = String::new();
+
+    for strip in 0..height.div_ceil(5) {
+        if strip > 0 {
+            code.push('z');
+        }
+
+        let mut empty_columns = 0;
+
+        for x in 0..width {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let column = (0..5).fold(0, |column, row| {
+                let y = (strip * 5 + row) as i32;
+                column | usize::from(cells.contains(&(y, x as i32))) << row
+            });
+
+            if column == 0 {
+                empty_columns += 1;
+                continue;
+            }
+
+            while empty_columns > 0 {
+                match empty_columns {
+                    1 => code.push('0'),
+                    2 => code.push('w'),
+                    3 => code.push('x'),
+                    _ => {
+                        let run = empty_columns.min(39);
+                        code.push('y');
+                        code.push(char::from(APGCODE_DIGITS[run - 4]));
+                        empty_columns -= run;
+                        continue;
+                    }
+                }
+                empty_columns = 0;
+            }
+
+            code.push(char::from(APGCODE_DIGITS[column]));
+        }
+    }
+
+    code
+}
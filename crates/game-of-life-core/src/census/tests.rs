@@ -0,0 +1,135 @@
+use super::*;
+use crate::pattern::{Orientation, Pattern};
+
+/// Takes a census of a board with one pattern on it, stamped at the given place.
+fn census_of(plaintext: &str, at: Coordinates) -> Census {
+    let mut state = State::empty(16, 16);
+    Pattern::parse_plaintext(plaintext)
+        .unwrap()
+        .stamp(&mut state, at, Orientation::default());
+    Census::take(&state).unwrap()
+}
+
+fn apgcode(plaintext: &str) -> String {
+    let census = census_of(plaintext, Coordinates { y: 4, x: 4 });
+    assert_eq!(census.objects().len(), 1, "{plaintext}");
+    census.objects()[0].apgcode.clone()
+}
+
+#[test]
+fn identifies_common_objects() {
+    for (plaintext, expected) in [
+        ("OO\nOO\n", "block"),
+        (".OO.\nO..O\n.OO.\n", "beehive"),
+        (".OO.\nO..O\n.O.O\n..O.\n", "loaf"),
+        ("OO.\nO.O\n.O.\n", "boat"),
+        (".O.\nO.O\n.O.\n", "tub"),
+        (".OO.\nO..O\nO..O\n.OO.\n", "pond"),
+        ("OO.\nO.O\n.OO\n", "ship"),
+        ("OOO\n", "blinker"),
+        (".OOO\nOOO.\n", "toad"),
+        ("OO..\nO...\n...O\n..OO\n", "beacon"),
+        (".O.\n..O\nOOO\n", "glider"),
+        (".O..O\nO....\nO...O\nOOOO.\n", "lightweight spaceship"),
+        (
+            "...O..\n.O...O\nO.....\nO....O\nOOOOO.\n",
+            "middleweight spaceship",
+        ),
+        (
+            "...OO..\n.O....O\nO......\nO.....O\nOOOOOO.\n",
+            "heavyweight spaceship",
+        ),
+    ] {
+        assert_eq!(name(&apgcode(plaintext)), Some(expected), "{plaintext}");
+    }
+}
+
+#[test]
+fn apgcodes_ignore_orientation_and_phase() {
+    // A glider in another phase, travelling up and to the left
+    assert_eq!(apgcode("OO.\nO.O\nO..\n"), "xq4_153");
+    assert_eq!(apgcode("O\nO\nO\n"), "xp2_7");
+    assert_eq!(apgcode(".O.\nO.O\nO.O\n.O.\n"), "xs6_696");
+}
+
+#[test]
+fn apgcodes_of_tall_objects_span_strips() {
+    // A long^4 ship, which is six cells tall in every orientation
+    assert_eq!(
+        apgcode("OO....\nO.O...\n.O.O..\n..O.O.\n...O.O\n....OO\n"),
+        "xs12_g8ka53z11"
+    );
+}
+
+#[test]
+fn counts_objects() {
+    let mut state = State::empty(24, 12);
+    for (plaintext, at) in [
+        ("OO\nOO\n", Coordinates { y: 1, x: 1 }),
+        ("OO\nOO\n", Coordinates { y: 1, x: 8 }),
+        ("OOO\n", Coordinates { y: 8, x: 2 }),
+        ("OO\nOO\n", Coordinates { y: 8, x: 16 }),
+    ] {
+        Pattern::parse_plaintext(plaintext)
+            .unwrap()
+            .stamp(&mut state, at, Orientation::default());
+    }
+
+    let census = Census::take(&state).unwrap();
+
+    assert_eq!(census.counts(), vec![("xs4_33", 3), ("xp2_7", 1)]);
+    assert_eq!(census.count("xs4_33"), 3);
+    assert_eq!(census.to_string(), "3 block (xs4_33)\n1 blinker (xp2_7)");
+}
+
+#[test]
+fn objects_wrap_around_a_torus() {
+    // A block split across the corners of the board
+    let mut state = State::empty(16, 16);
+    for index in [0, 15, 15 * 16, 16 * 16 - 1] {
+        state.replace_at_index(index, CellState::Alive);
+    }
+
+    let census = Census::take(&state).unwrap();
+
+    assert_eq!(census.counts(), vec![("xs4_33", 1)]);
+    assert_eq!(
+        census.objects()[0].cells,
+        [(-1, -1), (-1, 0), (0, -1), (0, 0)]
+            .map(|(y, x)| Coordinates { y, x })
+            .to_vec()
+    );
+}
+
+#[test]
+fn objects_beyond_the_viewport_are_counted() {
+    let mut state = State::empty(8, 8).with_storage(Storage::Unbounded);
+    Pattern::parse_plaintext(".O.\n..O\nOOO\n").unwrap().stamp(
+        &mut state,
+        Coordinates { y: 2, x: 2 },
+        Orientation::default(),
+    );
+    for _ in 0..40 {
+        state.step();
+    }
+
+    assert_eq!(Census::take(&state).unwrap().counts(), vec![("xq4_153", 1)]);
+}
+
+#[test]
+fn empty_boards_have_no_objects() {
+    assert_eq!(
+        Census::take(&State::empty(4, 4)).unwrap(),
+        Census::default()
+    );
+}
+
+#[test]
+fn unsupported_rules_are_rejected() {
+    let state = State::empty(4, 4);
+
+    assert!(matches!(
+        Census::take(&state.with_rule(Rule::BRIANS_BRAIN)),
+        Err(UnsupportedRuleError::TooManyStates(_))
+    ));
+}
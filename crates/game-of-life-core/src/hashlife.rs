@@ -0,0 +1,551 @@
+use crate::{
+    boundary::Boundary,
+    neighbourhood,
+    rule::Rule,
+    state::{CellState, State, Storage},
+    Coordinates,
+};
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// The largest level the quadtree can grow to, so that positions fit in an `i64`.
+const MAX_LEVEL: u8 = 60;
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct Quadrants {
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId,
+}
+
+/// A square of `2^level` by `2^level` cells, made of four squares one level lower.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    level: u8,
+    quadrants: Quadrants,
+    population: u64,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum UnsupportedRuleError {
+    #[error("hashlife can't simulate {0}, as empty space would not stay empty")]
+    BirthFromNothing(Rule),
+    #[error("hashlife can't simulate {0}, as it only supports cells which are alive or dead")]
+    TooManyStates(Rule),
+    #[error("hashlife can't simulate {0}, as it doesn't keep track of species")]
+    Species(Rule),
+    #[error("hashlife can't simulate the {0} neighbourhood, as it only supports the eight surrounding cells")]
+    Neighbourhood(String),
+}
+
+/// An engine using Gosper's hashlife algorithm, which simulates an unbounded plane as a quadtree of shared,
+/// memoized squares.
+///
+/// Repeated structure in both space and time is only computed once, so [`HashLife::step_by`] can jump to
+/// generations far beyond what stepping a [`State`] one generation at a time could reach.
+pub struct HashLife {
+    nodes: Vec<Node>,
+    /// Finds the existing node with the given quadrants, so each distinct square is only stored once
+    node_ids: HashMap<Quadrants, NodeId>,
+    /// The centre of a node after `2^n` generations, keyed by the node and `n`
+    results: HashMap<(NodeId, u8), NodeId>,
+    /// The empty node of each level
+    empty: Vec<NodeId>,
+    root: NodeId,
+    /// The position of the top left corner of the root
+    origin: Coordinates<i64>,
+    generation: u64,
+    rule: Rule,
+    /// The size, position and boundary of the board the engine was created from, used when converting back into a
+    /// [`State`]
+    width: usize,
+    height: usize,
+    viewport: Coordinates<i64>,
+    boundary: Boundary,
+    /// Whether the board was unbounded, so every alive cell is kept when converting back into a [`State`]
+    unbounded: bool,
+}
+
+impl HashLife {
+    /// Creates an engine from a board, which is placed on an unbounded plane with its top left corner at its
+    /// [`State::viewport`], along with every alive cell beyond the viewport of an unbounded board.
+    ///
+    /// The boundary of the board is not used, as the plane has no edges.
+    ///
+    /// # Errors
+    /// When the rule causes cells with no alive neighbours to be born, as empty space would then not stay empty, or
+    /// when the rule has dying states, or when the neighbourhood is not the eight surrounding cells.
+    pub fn from_state(state: &State) -> Result<Self, UnsupportedRuleError> {
+        let rule = state.rule();
+        if rule.is_born(0) {
+            return Err(UnsupportedRuleError::BirthFromNothing(rule));
+        }
+        if rule.states() > 2 {
+            return Err(UnsupportedRuleError::TooManyStates(rule));
+        }
+        if rule.species() > 1 {
+            return Err(UnsupportedRuleError::Species(rule));
+        }
+        if !neighbourhood::is_moore(state.neighbourhood()) {
+            return Err(UnsupportedRuleError::Neighbourhood(
+                state.neighbourhood().to_string(),
+            ));
+        }
+
+        let mut hashlife = Self {
+            nodes: [DEAD, ALIVE]
+                .map(|id| Node {
+                    level: 0,
+                    quadrants: Quadrants {
+                        nw: DEAD,
+                        ne: DEAD,
+                        sw: DEAD,
+                        se: DEAD,
+                    },
+                    population: u64::from(id),
+                })
+                .to_vec(),
+            node_ids: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            origin: state.viewport(),
+            generation: 0,
+            rule,
+            width: state.width(),
+            height: state.height(),
+            viewport: state.viewport(),
+            boundary: state.boundary(),
+            unbounded: state.storage() == Storage::Unbounded,
+        };
+
+        // The root covers the board and every alive cell, which can be beyond the viewport of an unbounded board
+        let alive = state.alive_positions();
+        #[allow(clippy::cast_possible_wrap)]
+        let bottom_right = Coordinates {
+            y: state.viewport().y + state.height().max(1) as i64 - 1,
+            x: state.viewport().x + state.width().max(1) as i64 - 1,
+        };
+        let (top_left, bottom_right) = alive.iter().fold(
+            (state.viewport(), bottom_right),
+            |(top_left, bottom_right), position| {
+                (
+                    Coordinates {
+                        y: top_left.y.min(position.y),
+                        x: top_left.x.min(position.x),
+                    },
+                    Coordinates {
+                        y: bottom_right.y.max(position.y),
+                        x: bottom_right.x.max(position.x),
+                    },
+                )
+            },
+        );
+
+        #[allow(clippy::cast_sign_loss)]
+        let size =
+            ((bottom_right.y - top_left.y).max(bottom_right.x - top_left.x) as u64 + 1).max(4);
+        #[allow(clippy::cast_possible_truncation)]
+        let level = size.next_power_of_two().trailing_zeros() as u8;
+
+        hashlife.origin = top_left;
+        hashlife.root = hashlife.build(level, top_left, alive);
+
+        Ok(hashlife)
+    }
+
+    /// Builds the node with its top left corner at `position` from the positions of the alive cells inside it.
+    fn build(
+        &mut self,
+        level: u8,
+        position: Coordinates<i64>,
+        alive: Vec<Coordinates<i64>>,
+    ) -> NodeId {
+        if alive.is_empty() {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return ALIVE;
+        }
+
+        let half = 1 << (level - 1);
+        let mut quadrants: [Vec<_>; 4] = Default::default();
+        for cell in alive {
+            let south = cell.y >= position.y + half;
+            let east = cell.x >= position.x + half;
+            quadrants[usize::from(south) * 2 + usize::from(east)].push(cell);
+        }
+
+        let [nw, ne, sw, se] = quadrants;
+        let nw = self.build(level - 1, position, nw);
+        let ne = self.build(
+            level - 1,
+            Coordinates {
+                y: position.y,
+                x: position.x + half,
+            },
+            ne,
+        );
+        let sw = self.build(
+            level - 1,
+            Coordinates {
+                y: position.y + half,
+                x: position.x,
+            },
+            sw,
+        );
+        let se = self.build(
+            level - 1,
+            Coordinates {
+                y: position.y + half,
+                x: position.x + half,
+            },
+            se,
+        );
+
+        self.join(nw, ne, sw, se)
+    }
+
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        let quadrants = Quadrants { nw, ne, sw, se };
+
+        if let Some(&id) = self.node_ids.get(&quadrants) {
+            return id;
+        }
+
+        let population = [nw, ne, sw, se].into_iter().fold(0_u64, |population, id| {
+            population.saturating_add(self.node(id).population)
+        });
+
+        #[allow(clippy::cast_possible_truncation)]
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node {
+            level: self.node(nw).level + 1,
+            quadrants,
+            population,
+        });
+        self.node_ids.insert(quadrants, id);
+
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level.into() {
+            let below = *self.empty.last().unwrap();
+            let id = self.join(below, below, below, below);
+            self.empty.push(id);
+        }
+
+        self.empty[usize::from(level)]
+    }
+
+    /// Gets the middle of a node, one level lower.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let Quadrants { nw, ne, sw, se } = self.node(id).quadrants;
+        let [nw, ne, sw, se] = [nw, ne, sw, se].map(|id| self.node(id).quadrants);
+        self.join(nw.se, ne.sw, sw.ne, se.nw)
+    }
+
+    /// Gets the middle of two nodes placed side by side, at the same level.
+    fn horizontal_centre(&mut self, west: NodeId, east: NodeId) -> NodeId {
+        let [west, east] = [west, east].map(|id| self.node(id).quadrants);
+        self.join(west.ne, east.nw, west.se, east.sw)
+    }
+
+    /// Gets the middle of two nodes placed one above the other, at the same level.
+    fn vertical_centre(&mut self, north: NodeId, south: NodeId) -> NodeId {
+        let [north, south] = [north, south].map(|id| self.node(id).quadrants);
+        self.join(north.sw, north.se, south.nw, south.ne)
+    }
+
+    /// Surrounds the root with empty space, placing it in the middle of a root one level higher.
+    fn expand(&mut self) {
+        let Node {
+            level, quadrants, ..
+        } = self.node(self.root);
+        assert!(
+            level < MAX_LEVEL,
+            "the pattern has grown too large to simulate"
+        );
+
+        let empty = self.empty(level - 1);
+        let Quadrants { nw, ne, sw, se } = quadrants;
+
+        let nw = self.join(empty, empty, empty, nw);
+        let ne = self.join(empty, empty, ne, empty);
+        let sw = self.join(empty, sw, empty, empty);
+        let se = self.join(se, empty, empty, empty);
+        self.root = self.join(nw, ne, sw, se);
+
+        let offset = 1 << (level - 1);
+        self.origin.x -= offset;
+        self.origin.y -= offset;
+    }
+
+    /// Steps the middle 2x2 cells of a 4x4 node by one generation.
+    fn step_leaves(&mut self, id: NodeId) -> NodeId {
+        let Quadrants { nw, ne, sw, se } = self.node(id).quadrants;
+        let mut cells = [[false; 4]; 4];
+
+        for (quadrant, x, y) in [(nw, 0, 0), (ne, 2, 0), (sw, 0, 2), (se, 2, 2)] {
+            let Quadrants { nw, ne, sw, se } = self.node(quadrant).quadrants;
+            cells[y][x] = nw == ALIVE;
+            cells[y][x + 1] = ne == ALIVE;
+            cells[y + 1][x] = sw == ALIVE;
+            cells[y + 1][x + 1] = se == ALIVE;
+        }
+
+        let [nw, ne, sw, se] = [(1, 1), (2, 1), (1, 2), (2, 2)].map(|(x, y): (usize, usize)| {
+            let alive_neighbours = (y - 1..=y + 1)
+                .flat_map(|neighbour_y| {
+                    (x - 1..=x + 1).map(move |neighbour_x| (neighbour_x, neighbour_y))
+                })
+                .filter(|&(neighbour_x, neighbour_y)| {
+                    (neighbour_x, neighbour_y) != (x, y) && cells[neighbour_y][neighbour_x]
+                })
+                .count();
+
+            NodeId::from(
+                self.rule
+                    .next_state(CellState::from(cells[y][x]), alive_neighbours)
+                    == CellState::Alive,
+            )
+        });
+
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Gets the middle of a node after `2^step` generations, where `step` is at most the node's level minus two.
+    fn result(&mut self, id: NodeId, step: u8) -> NodeId {
+        let Node {
+            level,
+            quadrants,
+            population,
+        } = self.node(id);
+
+        if population == 0 {
+            return self.empty(level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, step)) {
+            return result;
+        }
+
+        let result = if level == 2 {
+            self.step_leaves(id)
+        } else {
+            let Quadrants { nw, ne, sw, se } = quadrants;
+
+            // Nine overlapping squares, each half the size of the node
+            let north = self.horizontal_centre(nw, ne);
+            let west = self.vertical_centre(nw, sw);
+            let centre = self.centre(id);
+            let east = self.vertical_centre(ne, se);
+            let south = self.horizontal_centre(sw, se);
+            let squares = [nw, north, ne, west, centre, east, sw, south, se];
+
+            // At the largest step, each half of the generations is taken in a separate pass, otherwise the first
+            // pass only takes the middle of each square
+            let is_largest_step = step + 2 == level;
+            let squares = squares.map(|square| {
+                if is_largest_step {
+                    self.result(square, step - 1)
+                } else {
+                    self.centre(square)
+                }
+            });
+            let second_step = if is_largest_step { step - 1 } else { step };
+
+            let [nw, ne, sw, se] =
+                [(0, 1, 3, 4), (1, 2, 4, 5), (3, 4, 6, 7), (4, 5, 7, 8)].map(|(nw, ne, sw, se)| {
+                    let square = self.join(squares[nw], squares[ne], squares[sw], squares[se]);
+                    self.result(square, second_step)
+                });
+
+            self.join(nw, ne, sw, se)
+        };
+
+        self.results.insert((id, step), result);
+        result
+    }
+
+    /// Checks that the root is large enough to take a step of `2^step` generations, and that there is enough empty
+    /// space around the pattern that its growth over the step stays within the middle of the root.
+    fn can_step(&mut self, step: u8) -> bool {
+        let Node {
+            level, population, ..
+        } = self.node(self.root);
+
+        // The pattern must fit in the middle quarter of the root, as it can grow by `2^(level - 3)` cells in every
+        // direction and still be within the middle half
+        if level < step + 3 {
+            return false;
+        }
+
+        let centre = self.centre(self.root);
+        let centre = self.centre(centre);
+        self.node(centre).population == population
+    }
+
+    /// Advances the plane by any number of generations, jumping by powers of two.
+    ///
+    /// # Panics
+    /// When the pattern grows too large to be simulated.
+    pub fn step_by(&mut self, generations: u64) {
+        for step in (0..u64::BITS).filter(|bit| generations >> bit & 1 == 1) {
+            #[allow(clippy::cast_possible_truncation)]
+            let step = step as u8;
+
+            while !self.can_step(step) {
+                self.expand();
+            }
+
+            let level = self.node(self.root).level;
+            self.root = self.result(self.root, step);
+
+            let offset = 1 << (level - 2);
+            self.origin.x += offset;
+            self.origin.y += offset;
+            self.generation += 1 << step;
+        }
+    }
+
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    #[must_use]
+    pub fn population(&self) -> u64 {
+        self.node(self.root).population
+    }
+
+    #[must_use]
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// Copies a rectangle of the plane into a board, using the engine's rule.
+    #[must_use]
+    pub fn window(&self, top_left: Coordinates<i64>, width: usize, height: usize) -> State {
+        let mut cells = vec![CellState::Dead; width * height];
+        self.fill_window(self.root, self.origin, top_left, width, height, &mut cells);
+        State::from_cells(width, height, cells).with_rule(self.rule)
+    }
+
+    fn fill_window(
+        &self,
+        id: NodeId,
+        position: Coordinates<i64>,
+        top_left: Coordinates<i64>,
+        width: usize,
+        height: usize,
+        cells: &mut [CellState],
+    ) {
+        let Node {
+            level,
+            quadrants,
+            population,
+        } = self.node(id);
+        let size = 1_i64 << level;
+
+        #[allow(clippy::cast_possible_wrap)]
+        let (width, height) = (width as i64, height as i64);
+
+        let overlaps = position.x < top_left.x + width
+            && position.x + size > top_left.x
+            && position.y < top_left.y + height
+            && position.y + size > top_left.y;
+
+        if population == 0 || !overlaps {
+            return;
+        }
+
+        if level == 0 {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let index = ((position.y - top_left.y) * width + position.x - top_left.x) as usize;
+            cells[index] = CellState::Alive;
+            return;
+        }
+
+        let half = size / 2;
+        let Quadrants { nw, ne, sw, se } = quadrants;
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let (width, height) = (width as usize, height as usize);
+
+        for (quadrant, x, y) in [(nw, 0, 0), (ne, half, 0), (sw, 0, half), (se, half, half)] {
+            let position = Coordinates {
+                y: position.y + y,
+                x: position.x + x,
+            };
+            self.fill_window(quadrant, position, top_left, width, height, cells);
+        }
+    }
+
+    /// Finds the position of every alive cell in a node with its top left corner at `position`.
+    fn collect_alive(
+        &self,
+        id: NodeId,
+        position: Coordinates<i64>,
+        alive: &mut Vec<Coordinates<i64>>,
+    ) {
+        let Node {
+            level,
+            quadrants,
+            population,
+        } = self.node(id);
+
+        if population == 0 {
+            return;
+        }
+
+        if level == 0 {
+            alive.push(position);
+            return;
+        }
+
+        let half = 1_i64 << (level - 1);
+        let Quadrants { nw, ne, sw, se } = quadrants;
+
+        for (quadrant, x, y) in [(nw, 0, 0), (ne, half, 0), (sw, 0, half), (se, half, half)] {
+            let position = Coordinates {
+                y: position.y + y,
+                x: position.x + x,
+            };
+            self.collect_alive(quadrant, position, alive);
+        }
+    }
+
+    /// Copies the area covered by the board the engine was created from into a new board, with the same boundary.
+    ///
+    /// When that board was unbounded, the new board is unbounded too and keeps every alive cell, including those
+    /// which have moved beyond its viewport.
+    #[must_use]
+    pub fn to_state(&self) -> State {
+        let state = if self.unbounded {
+            let mut alive = Vec::new();
+            self.collect_alive(self.root, self.origin, &mut alive);
+            State::unbounded_from_alive(self.width, self.height, self.viewport, self.rule, alive)
+        } else {
+            self.window(self.viewport, self.width, self.height)
+                .with_viewport(self.viewport)
+        };
+
+        state
+            .with_boundary(self.boundary)
+            .with_generation(self.generation)
+    }
+}
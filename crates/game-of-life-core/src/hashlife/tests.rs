@@ -0,0 +1,152 @@
+use super::*;
+use crate::{
+    boundary::Boundary,
+    pattern::{Orientation, Pattern},
+};
+
+fn glider() -> Pattern {
+    Pattern::parse_plaintext(".O.\n..O\nOOO\n").unwrap()
+}
+
+fn stamped(pattern: &Pattern, width: usize, height: usize) -> State {
+    let mut state = State::empty(width, height);
+    pattern.stamp(
+        &mut state,
+        Coordinates { y: 0, x: 0 },
+        Orientation::default(),
+    );
+    state
+}
+
+#[test]
+fn round_trips_states() {
+    let state = State::from_seed(13, 7, 3, 0.5);
+    let hashlife = HashLife::from_state(&state).unwrap();
+
+    assert_eq!(hashlife.to_state().cells(), state.cells());
+    assert_eq!(hashlife.generation(), 0);
+}
+
+#[test]
+fn matches_state_stepping() {
+    for generations in [1, 2, 3, 7, 30, 64, 100] {
+        // The soup is placed in the middle of a large board with dead edges, which behaves like an unbounded plane
+        let soup = Pattern::from(&State::from_seed(16, 16, generations, 0.4));
+        let mut state = State::empty(96, 96).with_boundary(Boundary::Dead);
+        soup.stamp_centered(&mut state);
+
+        let mut hashlife = HashLife::from_state(&state).unwrap();
+        hashlife.step_by(generations);
+        for _ in 0..generations {
+            state.step();
+        }
+
+        assert_eq!(hashlife.generation(), generations);
+        assert_eq!(
+            hashlife.to_state().cells(),
+            state.cells(),
+            "after {generations} generations"
+        );
+    }
+}
+
+#[test]
+fn gliders_travel_far() {
+    let mut hashlife = HashLife::from_state(&stamped(&glider(), 3, 3)).unwrap();
+
+    hashlife.step_by(1_000_000);
+
+    // A glider moves one cell diagonally every four generations
+    let window = hashlife.window(
+        Coordinates {
+            y: 250_000,
+            x: 250_000,
+        },
+        3,
+        3,
+    );
+    assert_eq!(hashlife.population(), 5);
+    assert_eq!(
+        Pattern::from(&window).to_plaintext(),
+        glider().to_plaintext()
+    );
+}
+
+#[test]
+fn steps_accumulate() {
+    let mut hashlife = HashLife::from_state(&stamped(&glider(), 3, 3)).unwrap();
+
+    hashlife.step_by(0);
+    assert_eq!(hashlife.generation(), 0);
+
+    for _ in 0..4 {
+        hashlife.step_by(1);
+    }
+    hashlife.step_by(4);
+
+    let window = hashlife.window(Coordinates { y: 2, x: 2 }, 3, 3);
+    assert_eq!(hashlife.generation(), 8);
+    assert_eq!(
+        Pattern::from(&window).to_plaintext(),
+        glider().to_plaintext()
+    );
+}
+
+#[test]
+fn keeps_cells_beyond_the_viewport_of_unbounded_boards() {
+    let mut state = stamped(&glider(), 8, 8).with_storage(Storage::Unbounded);
+    state.set_viewport(Coordinates { y: 20, x: -30 });
+
+    let mut hashlife = HashLife::from_state(&state).unwrap();
+    hashlife.step_by(100);
+    for _ in 0..100 {
+        state.step();
+    }
+
+    let stepped = hashlife.to_state();
+    assert_eq!(stepped.storage(), Storage::Unbounded);
+    assert_eq!(stepped.viewport(), state.viewport());
+    assert_eq!(stepped.population(), 5);
+    assert_eq!(stepped.bounding_box(), state.bounding_box());
+    assert_eq!(stepped.cells(), state.cells());
+}
+
+#[test]
+fn keeps_the_boundary() {
+    for boundary in [Boundary::Dead, Boundary::Reflective] {
+        let state = stamped(&glider(), 8, 8).with_boundary(boundary);
+        let mut hashlife = HashLife::from_state(&state).unwrap();
+        hashlife.step_by(4);
+
+        let stepped = hashlife.to_state();
+        assert_eq!(stepped.boundary(), boundary);
+        assert_eq!(stepped.generation(), 4);
+    }
+}
+
+#[test]
+fn uses_the_rule() {
+    let state = stamped(&Pattern::parse_plaintext("OO\n").unwrap(), 2, 1).with_rule(Rule::SEEDS);
+    let mut hashlife = HashLife::from_state(&state).unwrap();
+
+    hashlife.step_by(1);
+
+    assert_eq!(hashlife.rule(), Rule::SEEDS);
+    assert_eq!(hashlife.population(), 4);
+}
+
+#[test]
+fn birth_on_zero_is_unsupported() {
+    let state = State::empty(4, 4).with_rule("B0/S".parse().unwrap());
+
+    assert!(HashLife::from_state(&state).is_err());
+}
+
+#[test]
+fn generations_rules_are_unsupported() {
+    let state = State::empty(4, 4).with_rule(Rule::BRIANS_BRAIN);
+    assert_eq!(
+        HashLife::from_state(&state).err(),
+        Some(UnsupportedRuleError::TooManyStates(Rule::BRIANS_BRAIN))
+    );
+}
@@ -3,10 +3,19 @@
 use state::CellState;
 use std::ops::{Add, Mul};
 
+pub mod age;
+pub mod boundary;
+pub mod census;
+pub mod hashlife;
+pub mod neighbourhood;
+pub mod pattern;
+pub mod rule;
+pub mod runner;
 pub mod state;
 pub mod ui;
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coordinates<T = i32> {
     pub y: T,
     pub x: T,
@@ -22,15 +31,23 @@ impl<T> Coordinates<T> {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CellRenderInfo {
     pub state: CellState,
+    /// The species of the cell, which is always 0 unless the rule has more than one species
+    pub species: u8,
     pub coordinates: Coordinates,
     pub needs_rerender: bool,
 }
 
 pub mod prelude {
     pub use super::{
-        state::{self, CellState, State},
+        boundary::Boundary,
+        neighbourhood::Neighbourhood,
+        pattern::Pattern,
+        rule::Rule,
+        runner::{Command, Controller, Runner},
+        state::{self, CellState, FrameKind, State, Status},
         ui::{Renderer, RendererBackend},
         CellRenderInfo, Coordinates,
     };
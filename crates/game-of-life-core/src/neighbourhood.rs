@@ -0,0 +1,274 @@
+use crate::{rule::MAX_NEIGHBOURS, Coordinates};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, u32 as radius},
+    combinator::{all_consuming, opt},
+    sequence::preceded,
+    IResult, Parser,
+};
+use std::{fmt, sync::Arc};
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// The shape of the cells a neighbourhood is made for, so frontends can draw them.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Tiling {
+    #[default]
+    Square,
+    /// Hexagons stored in a skewed grid, where each row is drawn half a cell to the left of the row above.
+    Hexagonal,
+    /// Triangles which alternate between pointing up and down, where the cell at `(0, 0)` points up.
+    Triangular,
+}
+
+/// Decides which cells are the neighbours of each cell, so the same stepping code can be used with any grid.
+pub trait Neighbourhood: fmt::Debug + fmt::Display + Send + Sync {
+    /// Gets the offsets from a cell to each of its neighbours, which may depend on where the cell is.
+    fn offsets(&self, coordinates: Coordinates) -> &[Coordinates];
+
+    /// Gets every offset any cell can have a neighbour at, which is used to find the cells a change can affect.
+    fn reach(&self) -> &[Coordinates] {
+        self.offsets(Coordinates { y: 0, x: 0 })
+    }
+
+    fn tiling(&self) -> Tiling {
+        Tiling::Square
+    }
+}
+
+/// Finds whether a neighbourhood is the standard eight surrounding cells, which faster engines rely on.
+pub(crate) fn is_moore(neighbourhood: &dyn Neighbourhood) -> bool {
+    let moore = Moore::default();
+    let origin = Coordinates { y: 0, x: 0 };
+
+    neighbourhood.tiling() == Tiling::Square
+        && neighbourhood.offsets(origin) == moore.offsets(origin)
+        && neighbourhood.reach() == moore.reach()
+}
+
+/// Every offset within a square of the given radius, excluding the cell itself.
+fn square_offsets(radius: i32, is_included: impl Fn(i32, i32) -> bool) -> Vec<Coordinates> {
+    (-radius..=radius)
+        .flat_map(|y| (-radius..=radius).map(move |x| Coordinates { y, x }))
+        .filter(|&Coordinates { y, x }| (y, x) != (0, 0) && is_included(y, x))
+        .collect()
+}
+
+/// The cells within a square around a cell, which is the eight surrounding cells with a radius of 1.
+///
+/// Larger radii give the box neighbourhoods of Larger than Life rules.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Moore {
+    radius: u32,
+    offsets: Vec<Coordinates>,
+}
+
+impl Moore {
+    /// # Panics
+    /// When the radius is 0, or so large that a cell would have more than 127 neighbours.
+    #[must_use]
+    pub fn new(radius: u32) -> Self {
+        let side = 2 * radius as usize + 1;
+        assert!(
+            radius > 0 && side * side - 1 <= MAX_NEIGHBOURS,
+            "a Moore neighbourhood must have a radius from 1 to 5"
+        );
+
+        #[allow(clippy::cast_possible_wrap)]
+        let offsets = square_offsets(radius as i32, |_, _| true);
+
+        Self { radius, offsets }
+    }
+}
+
+impl Default for Moore {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Neighbourhood for Moore {
+    fn offsets(&self, _: Coordinates) -> &[Coordinates] {
+        &self.offsets
+    }
+}
+
+impl fmt::Display for Moore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("moore")?;
+        if self.radius > 1 {
+            write!(f, ":{}", self.radius)?;
+        }
+        Ok(())
+    }
+}
+
+/// The cells within a diamond around a cell, which is the four orthogonally adjacent cells with a radius of 1.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct VonNeumann {
+    radius: u32,
+    offsets: Vec<Coordinates>,
+}
+
+impl VonNeumann {
+    /// # Panics
+    /// When the radius is 0, or so large that a cell would have more than 127 neighbours.
+    #[must_use]
+    pub fn new(radius: u32) -> Self {
+        let radius_size = radius as usize;
+        assert!(
+            radius > 0 && 2 * radius_size * (radius_size + 1) <= MAX_NEIGHBOURS,
+            "a von Neumann neighbourhood must have a radius from 1 to 7"
+        );
+
+        #[allow(clippy::cast_possible_wrap)]
+        let radius_offset = radius as i32;
+        let offsets = square_offsets(radius_offset, |y, x| y.abs() + x.abs() <= radius_offset);
+
+        Self { radius, offsets }
+    }
+}
+
+impl Default for VonNeumann {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl Neighbourhood for VonNeumann {
+    fn offsets(&self, _: Coordinates) -> &[Coordinates] {
+        &self.offsets
+    }
+}
+
+impl fmt::Display for VonNeumann {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("von-neumann")?;
+        if self.radius > 1 {
+            write!(f, ":{}", self.radius)?;
+        }
+        Ok(())
+    }
+}
+
+/// The six cells around a hexagon, stored in a skewed grid.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Hexagonal;
+
+impl Hexagonal {
+    const OFFSETS: [Coordinates; 6] = [
+        Coordinates { y: -1, x: -1 },
+        Coordinates { y: -1, x: 0 },
+        Coordinates { y: 0, x: -1 },
+        Coordinates { y: 0, x: 1 },
+        Coordinates { y: 1, x: 0 },
+        Coordinates { y: 1, x: 1 },
+    ];
+}
+
+impl Neighbourhood for Hexagonal {
+    fn offsets(&self, _: Coordinates) -> &[Coordinates] {
+        &Self::OFFSETS
+    }
+
+    fn tiling(&self) -> Tiling {
+        Tiling::Hexagonal
+    }
+}
+
+impl fmt::Display for Hexagonal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("hex")
+    }
+}
+
+/// The three cells sharing an edge with a triangle, which are below a triangle pointing up, or above a triangle
+/// pointing down, along with the cells to either side.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Triangular;
+
+impl Triangular {
+    const UP: [Coordinates; 3] = [
+        Coordinates { y: 0, x: -1 },
+        Coordinates { y: 0, x: 1 },
+        Coordinates { y: 1, x: 0 },
+    ];
+    const DOWN: [Coordinates; 3] = [
+        Coordinates { y: -1, x: 0 },
+        Coordinates { y: 0, x: -1 },
+        Coordinates { y: 0, x: 1 },
+    ];
+    const REACH: [Coordinates; 4] = [
+        Coordinates { y: -1, x: 0 },
+        Coordinates { y: 0, x: -1 },
+        Coordinates { y: 0, x: 1 },
+        Coordinates { y: 1, x: 0 },
+    ];
+}
+
+impl Neighbourhood for Triangular {
+    fn offsets(&self, Coordinates { y, x }: Coordinates) -> &[Coordinates] {
+        if (x + y).rem_euclid(2) == 0 {
+            &Self::UP
+        } else {
+            &Self::DOWN
+        }
+    }
+
+    fn reach(&self) -> &[Coordinates] {
+        &Self::REACH
+    }
+
+    fn tiling(&self) -> Tiling {
+        Tiling::Triangular
+    }
+}
+
+impl fmt::Display for Triangular {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("triangular")
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "unknown neighbourhood {0:?}, expected one of moore, von-neumann, hex or triangular, \
+     where moore and von-neumann can have a radius such as moore:2"
+)]
+pub struct ParseNeighbourhoodError(String);
+
+/// Parses the name of a neighbourhood, as written by its `Display` implementation.
+///
+/// # Errors
+/// When the name is unknown, or the radius is out of range.
+#[allow(clippy::module_name_repetitions)]
+pub fn parse_neighbourhood(input: &str) -> Result<Arc<dyn Neighbourhood>, ParseNeighbourhoodError> {
+    all_consuming(neighbourhood)(input.trim())
+        .ok()
+        .and_then(|(_, neighbourhood)| neighbourhood)
+        .ok_or_else(|| ParseNeighbourhoodError(input.to_string()))
+}
+
+/// Parses a neighbourhood, which is `None` when the radius is out of range.
+fn neighbourhood(input: &str) -> IResult<&str, Option<Arc<dyn Neighbourhood>>> {
+    let radius = || opt(preceded(char(':'), radius)).map(|radius| radius.unwrap_or(1));
+
+    alt((
+        preceded(tag("moore"), radius()).map(|radius| {
+            (1..=5)
+                .contains(&radius)
+                .then(|| Arc::new(Moore::new(radius)) as Arc<dyn Neighbourhood>)
+        }),
+        preceded(tag("von-neumann"), radius()).map(|radius| {
+            (1..=7)
+                .contains(&radius)
+                .then(|| Arc::new(VonNeumann::new(radius)) as Arc<dyn Neighbourhood>)
+        }),
+        tag("hex").map(|_| Some(Arc::new(Hexagonal) as Arc<dyn Neighbourhood>)),
+        tag("triangular").map(|_| Some(Arc::new(Triangular) as Arc<dyn Neighbourhood>)),
+    ))
+    .parse(input)
+}
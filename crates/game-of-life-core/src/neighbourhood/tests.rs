@@ -0,0 +1,61 @@
+use super::*;
+
+#[test]
+fn neighbourhoods_have_expected_sizes() {
+    let origin = Coordinates { y: 0, x: 0 };
+
+    assert_eq!(Moore::default().offsets(origin).len(), 8);
+    assert_eq!(Moore::new(2).offsets(origin).len(), 24);
+    assert_eq!(Moore::new(5).offsets(origin).len(), 120);
+    assert_eq!(VonNeumann::default().offsets(origin).len(), 4);
+    assert_eq!(VonNeumann::new(2).offsets(origin).len(), 12);
+    assert_eq!(Hexagonal.offsets(origin).len(), 6);
+    assert_eq!(Triangular.offsets(origin).len(), 3);
+}
+
+#[test]
+fn triangles_alternate() {
+    let up = Triangular.offsets(Coordinates { y: 0, x: 0 });
+    let down = Triangular.offsets(Coordinates { y: 0, x: 1 });
+
+    assert!(up.contains(&Coordinates { y: 1, x: 0 }));
+    assert!(down.contains(&Coordinates { y: -1, x: 0 }));
+    assert_eq!(Triangular.offsets(Coordinates { y: 1, x: 1 }), up);
+}
+
+#[test]
+#[should_panic(expected = "radius from 1 to 5")]
+fn moore_radius_is_bounded() {
+    let _ = Moore::new(6);
+}
+
+#[test]
+fn neighbourhoods_parse() {
+    for name in [
+        "moore",
+        "moore:3",
+        "von-neumann",
+        "von-neumann:7",
+        "hex",
+        "triangular",
+    ] {
+        assert_eq!(parse_neighbourhood(name).unwrap().to_string(), name);
+    }
+
+    assert_eq!(parse_neighbourhood("moore:1").unwrap().to_string(), "moore");
+
+    for name in ["", "square", "moore:0", "moore:6", "von-neumann:8", "hex:2"] {
+        assert!(
+            parse_neighbourhood(name).is_err(),
+            "{name:?} should not parse"
+        );
+    }
+}
+
+#[test]
+fn only_the_standard_neighbourhood_is_moore() {
+    assert!(is_moore(&Moore::default()));
+    assert!(!is_moore(&Moore::new(2)));
+    assert!(!is_moore(&VonNeumann::default()));
+    assert!(!is_moore(&Hexagonal));
+}
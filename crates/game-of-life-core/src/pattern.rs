@@ -0,0 +1,522 @@
+use crate::{
+    rule::{ParseRuleError, Rule},
+    state::{CellState, State},
+    Coordinates,
+};
+use nom::{
+    bytes::complete::tag,
+    character::complete::{digit1, space0},
+    combinator::{all_consuming, map_res, opt, rest},
+    sequence::{delimited, preceded, tuple},
+    IResult, Parser,
+};
+use std::{fmt::Write, str::FromStr};
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// The longest line written to an RLE file, as recommended by Golly.
+const RLE_LINE_LENGTH: usize = 70;
+
+/// The most cells a parsed pattern can have, so a malformed or malicious file can't make it allocate gigabytes.
+pub const MAX_CELLS: usize = 1 << 24;
+
+/// A rectangular pattern of cells, such as a glider or a gun, which can be stamped into a [`State`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Pattern {
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+    /// The rule the pattern was designed for, when one was given
+    pub rule: Option<Rule>,
+    width: usize,
+    height: usize,
+    cells: Vec<CellState>,
+}
+
+/// A rotation of a pattern, in steps of a quarter turn clockwise.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise,
+    Half,
+    Anticlockwise,
+}
+
+/// How a pattern is placed into a board, where it is flipped before it is rotated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Orientation {
+    pub rotation: Rotation,
+    /// Whether the pattern is mirrored from left to right
+    pub flipped: bool,
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub enum PatternError {
+    #[error("missing RLE header line (such as `x = 3, y = 3`)")]
+    MissingHeader,
+    #[error("invalid RLE header line {0:?}")]
+    InvalidHeader(String),
+    #[error(transparent)]
+    Rule(#[from] ParseRuleError),
+    #[error("unexpected {character:?} on line {line}")]
+    UnexpectedCharacter { character: char, line: usize },
+    #[error("the pattern has cells outside of its {width}x{height} size")]
+    OutOfBounds { width: usize, height: usize },
+    #[error("the pattern is {width}x{height}, which is more than the {MAX_CELLS} cells a pattern can have")]
+    TooLarge { width: usize, height: usize },
+    #[error("the run count on line {line} is too large")]
+    RunTooLong { line: usize },
+}
+
+impl Pattern {
+    /// Creates a pattern from its cells, ordered row by row.
+    ///
+    /// # Panics
+    /// When the number of cells does not match the size of the pattern.
+    #[must_use]
+    pub fn new(width: usize, height: usize, cells: Vec<CellState>) -> Self {
+        assert_eq!(
+            cells.len(),
+            width * height,
+            "a {width}x{height} pattern must have {} cells",
+            width * height
+        );
+
+        Self {
+            name: None,
+            comments: Vec::new(),
+            rule: None,
+            width,
+            height,
+            cells,
+        }
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub fn get(&self, x: usize, y: usize) -> Option<CellState> {
+        if x < self.width && y < self.height {
+            Some(self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Parses a pattern in either Golly RLE or plaintext (`.cells`) format, detecting which is used.
+    ///
+    /// # Errors
+    /// When the input is not a valid pattern.
+    pub fn parse(input: &str) -> Result<Self, PatternError> {
+        let is_rle = input
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(['#', '!']))
+            .map_or(input.trim_start().starts_with('#'), |line| {
+                line.starts_with('x')
+            });
+
+        if is_rle {
+            Self::parse_rle(input)
+        } else {
+            Self::parse_plaintext(input)
+        }
+    }
+
+    /// Parses a pattern in Golly's RLE format, including its `#N` and `#C` headers and its rule.
+    ///
+    /// Patterns for rules with more than two states use `.` for dead cells, `A` for alive cells and `B` to `X` for
+    /// each stage of dying.
+    ///
+    /// # Errors
+    /// When the input is not a valid RLE pattern.
+    pub fn parse_rle(input: &str) -> Result<Self, PatternError> {
+        let mut name = None;
+        let mut comments = Vec::new();
+        let mut lines = input.lines().enumerate();
+
+        let (width, height, rule) = loop {
+            let Some((_, line)) = lines.next() else {
+                return Err(PatternError::MissingHeader);
+            };
+            let line = line.trim();
+
+            if let Some(header) = line.strip_prefix('#') {
+                let mut chars = header.chars();
+                let kind = chars.next();
+                let value = chars.as_str().trim().to_string();
+
+                match kind {
+                    Some('N') => name = Some(value),
+                    Some('C' | 'c') => comments.push(value),
+                    _ => {}
+                }
+            } else if !line.is_empty() {
+                let (_, (width, height, rule)) = all_consuming(rle_header)(line)
+                    .map_err(|_| PatternError::InvalidHeader(line.to_string()))?;
+                // Golly can add a bounded grid to the rule, such as `B3/S23:T20,20`, which is ignored
+                let rule = rule.and_then(|rule| rule.split(':').next());
+                break (width, height, rule.map(str::parse).transpose()?);
+            }
+        };
+
+        let mut cells = vec![CellState::Dead; checked_size(width, height)?];
+        let out_of_bounds = PatternError::OutOfBounds { width, height };
+        let (mut x, mut y, mut run) = (0_usize, 0_usize, 0_usize);
+
+        'lines: for (line_index, line) in lines {
+            for character in line.chars() {
+                if let Some(digit) = character.to_digit(10) {
+                    run = run
+                        .checked_mul(10)
+                        .and_then(|run| run.checked_add(digit as usize))
+                        .ok_or(PatternError::RunTooLong {
+                            line: line_index + 1,
+                        })?;
+                    continue;
+                }
+
+                let count = run.max(1);
+
+                match character {
+                    'b' | '.' => x = x.checked_add(count).ok_or(out_of_bounds.clone())?,
+                    'o' | 'A'..='X' => {
+                        let end = x.checked_add(count).filter(|&end| end <= width);
+                        let Some(end) = end.filter(|_| y < height) else {
+                            return Err(out_of_bounds);
+                        };
+                        cells[y * width + x..y * width + end].fill(rle_state(character));
+                        x = end;
+                    }
+                    '$' => {
+                        x = 0;
+                        y = y.checked_add(count).ok_or(out_of_bounds.clone())?;
+                    }
+                    '!' => break 'lines,
+                    character if character.is_whitespace() => {}
+                    character => {
+                        return Err(PatternError::UnexpectedCharacter {
+                            character,
+                            line: line_index + 1,
+                        })
+                    }
+                }
+
+                run = 0;
+            }
+        }
+
+        Ok(Self {
+            name,
+            comments,
+            rule,
+            ..Self::new(width, height, cells)
+        })
+    }
+
+    /// Parses a pattern in the plaintext (`.cells`) format, where `O` is an alive cell and `.` is a dead cell.
+    ///
+    /// # Errors
+    /// When the input has a character other than `O` or `.` outside of its comments.
+    pub fn parse_plaintext(input: &str) -> Result<Self, PatternError> {
+        let mut name = None;
+        let mut comments = Vec::new();
+        let mut rows = Vec::new();
+
+        for (line_index, line) in input.lines().enumerate() {
+            let line = line.trim_end();
+
+            if let Some(comment) = line.strip_prefix('!') {
+                if let Some(pattern_name) = comment.strip_prefix("Name:") {
+                    name = Some(pattern_name.trim().to_string());
+                } else {
+                    comments.push(comment.trim().to_string());
+                }
+                continue;
+            }
+
+            let row = line
+                .chars()
+                .map(|character| match character {
+                    'O' | '*' => Ok(CellState::Alive),
+                    '.' => Ok(CellState::Dead),
+                    character => Err(PatternError::UnexpectedCharacter {
+                        character,
+                        line: line_index + 1,
+                    }),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            rows.push(row);
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let height = rows.len();
+        checked_size(width, height)?;
+        let cells = rows
+            .into_iter()
+            .flat_map(|mut row| {
+                row.resize(width, CellState::Dead);
+                row
+            })
+            .collect();
+
+        Ok(Self {
+            name,
+            comments,
+            ..Self::new(width, height, cells)
+        })
+    }
+
+    /// Writes the pattern in Golly's RLE format.
+    #[must_use]
+    pub fn to_rle(&self) -> String {
+        let mut output = String::new();
+
+        if let Some(name) = &self.name {
+            writeln!(output, "#N {name}").unwrap();
+        }
+        for comment in &self.comments {
+            writeln!(output, "#C {comment}").unwrap();
+        }
+
+        write!(output, "x = {}, y = {}", self.width, self.height).unwrap();
+        if let Some(rule) = self.rule {
+            write!(output, ", rule = {rule}").unwrap();
+        }
+        output.push('\n');
+
+        // Patterns with dying cells use the multi-state tags, where `.` is dead, `A` is alive and `B` onwards are dying
+        let multi_state = self
+            .cells
+            .iter()
+            .any(|state| matches!(state, CellState::Dying(_)));
+
+        // Each run is a count and a tag, where trailing dead cells and empty rows are merged into the next `$`
+        let mut runs = Vec::<(usize, char)>::new();
+        let mut push_run = |count, tag| match runs.last_mut() {
+            Some((previous_count, previous_tag)) if *previous_tag == tag => {
+                *previous_count += count;
+            }
+            _ => runs.push((count, tag)),
+        };
+
+        for (y, row) in self.cells.chunks(self.width.max(1)).enumerate() {
+            if y > 0 {
+                push_run(1, '$');
+            }
+
+            let alive_length = row
+                .iter()
+                .rposition(|&state| state != CellState::Dead)
+                .map_or(0, |index| index + 1);
+
+            for &state in &row[..alive_length] {
+                push_run(1, rle_tag(state, multi_state));
+            }
+        }
+
+        if runs.last().is_some_and(|&(_, tag)| tag == '$') {
+            runs.pop();
+        }
+
+        let mut line_length = 0;
+        for run in runs
+            .into_iter()
+            .map(|(count, tag)| {
+                if count == 1 {
+                    tag.to_string()
+                } else {
+                    format!("{count}{tag}")
+                }
+            })
+            .chain(std::iter::once("!".to_string()))
+        {
+            if line_length + run.len() > RLE_LINE_LENGTH {
+                output.push('\n');
+                line_length = 0;
+            }
+            line_length += run.len();
+            output.push_str(&run);
+        }
+        output.push('\n');
+
+        output
+    }
+
+    /// Writes the pattern in the plaintext (`.cells`) format.
+    #[must_use]
+    pub fn to_plaintext(&self) -> String {
+        let mut output = String::new();
+
+        if let Some(name) = &self.name {
+            writeln!(output, "!Name: {name}").unwrap();
+        }
+        for comment in &self.comments {
+            writeln!(output, "!{comment}").unwrap();
+        }
+
+        for row in self.cells.chunks(self.width.max(1)) {
+            output.extend(row.iter().map(
+                |&state| {
+                    if state == CellState::Alive {
+                        'O'
+                    } else {
+                        '.'
+                    }
+                },
+            ));
+            output.push('\n');
+        }
+
+        output
+    }
+
+    /// Creates a copy of the pattern that has been flipped and rotated.
+    #[must_use]
+    pub fn oriented(&self, Orientation { rotation, flipped }: Orientation) -> Self {
+        let Self { width, height, .. } = *self;
+
+        let (new_width, new_height) = match rotation {
+            Rotation::None | Rotation::Half => (width, height),
+            Rotation::Clockwise | Rotation::Anticlockwise => (height, width),
+        };
+
+        let cells = (0..new_height)
+            .flat_map(|y| (0..new_width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                // Find the cell that is moved to (x, y) by undoing the rotation, and then the flip
+                let (x, y) = match rotation {
+                    Rotation::None => (x, y),
+                    Rotation::Clockwise => (y, height - 1 - x),
+                    Rotation::Half => (width - 1 - x, height - 1 - y),
+                    Rotation::Anticlockwise => (width - 1 - y, x),
+                };
+                let x = if flipped { width - 1 - x } else { x };
+
+                self.cells[y * width + x]
+            })
+            .collect();
+
+        Self {
+            name: self.name.clone(),
+            comments: self.comments.clone(),
+            rule: self.rule,
+            ..Self::new(new_width, new_height, cells)
+        }
+    }
+
+    /// Stamps the pattern into a board, with its top left corner at the given coordinates.
+    ///
+    /// Both alive and dead cells of the pattern replace the cells of the board, and cells that would be placed
+    /// outside of the board are ignored.
+    pub fn stamp(&self, state: &mut State, at: Coordinates, orientation: Orientation) {
+        let pattern = self.oriented(orientation);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let (width, height) = (state.width() as i32, state.height() as i32);
+
+        for (index, &cell) in pattern.cells.iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+            let coordinates = Coordinates {
+                x: at.x + (index % pattern.width) as i32,
+                y: at.y + (index / pattern.width) as i32,
+            };
+
+            if (0..width).contains(&coordinates.x) && (0..height).contains(&coordinates.y) {
+                #[allow(clippy::cast_sign_loss)]
+                state.replace_at_index(coordinates.to_index(width) as usize, cell);
+            }
+        }
+    }
+
+    /// Stamps the pattern into the middle of a board.
+    pub fn stamp_centered(&self, state: &mut State) {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let at = Coordinates {
+            x: (state.width() as i32 - self.width as i32) / 2,
+            y: (state.height() as i32 - self.height as i32) / 2,
+        };
+
+        self.stamp(state, at, Orientation::default());
+    }
+}
+
+impl From<&State> for Pattern {
+    fn from(state: &State) -> Self {
+        Self {
+            rule: Some(state.rule()),
+            ..Self::new(state.width(), state.height(), state.cells())
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+/// Gets the number of cells in a pattern of some size, checking it is no more than [`MAX_CELLS`] before anything that
+/// size is allocated.
+fn checked_size(width: usize, height: usize) -> Result<usize, PatternError> {
+    width
+        .checked_mul(height)
+        .filter(|&size| size <= MAX_CELLS)
+        .ok_or(PatternError::TooLarge { width, height })
+}
+
+/// Parses an RLE header line, such as `x = 3, y = 3, rule = B3/S23`.
+fn rle_header(input: &str) -> IResult<&str, (usize, usize, Option<&str>)> {
+    let number = || map_res(digit1, str::parse::<usize>);
+    let field = |name| {
+        delimited(
+            tuple((space0, tag(name), space0, tag("="), space0)),
+            number(),
+            space0,
+        )
+    };
+
+    tuple((
+        field("x"),
+        preceded(tag(","), field("y")),
+        opt(preceded(
+            tuple((tag(","), space0, tag("rule"), space0, tag("="), space0)),
+            rest.map(str::trim),
+        )),
+    ))
+    .parse(input)
+}
+
+/// Gets the state of a filled RLE tag, which is `o` or `A` for alive cells and `B` onwards for dying cells.
+fn rle_state(tag: char) -> CellState {
+    match tag {
+        'o' | 'A' => CellState::Alive,
+        #[allow(clippy::cast_possible_truncation)]
+        stage => CellState::Dying((u32::from(stage) - u32::from('A')) as u8),
+    }
+}
+
+/// Gets the RLE tag of a cell, where stages of dying past `X` can't be written and are clamped.
+fn rle_tag(state: CellState, multi_state: bool) -> char {
+    match (state, multi_state) {
+        (CellState::Dead, false) => 'b',
+        (CellState::Alive, false) => 'o',
+        (CellState::Dead, true) => '.',
+        (CellState::Alive, true) => 'A',
+        (CellState::Dying(dying_stage), _) => char::from(b'A' + dying_stage.min(23)),
+    }
+}
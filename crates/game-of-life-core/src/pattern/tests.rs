@@ -0,0 +1,232 @@
+use super::*;
+
+const GLIDER_RLE: &str = "#N Glider
+#C The smallest spaceship.
+#C www.conwaylife.com/wiki/index.php?title=Glider
+x = 3, y = 3, rule = B3/S23
+bob$2bo$3o!
+";
+
+const GLIDER_PLAINTEXT: &str = "!Name: Glider
+!The smallest spaceship.
+.O.
+..O
+OOO
+";
+
+fn glider_cells() -> Vec<CellState> {
+    [
+        [false, true, false],
+        [false, false, true],
+        [true, true, true],
+    ]
+    .into_iter()
+    .flatten()
+    .map(CellState::from)
+    .collect()
+}
+
+#[test]
+fn rle_parses() {
+    let pattern = Pattern::parse_rle(GLIDER_RLE).unwrap();
+
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(pattern.comments.len(), 2);
+    assert_eq!(pattern.rule, Some(Rule::CONWAY));
+    assert_eq!((pattern.width(), pattern.height()), (3, 3));
+    assert_eq!(pattern.cells, glider_cells());
+}
+
+#[test]
+fn rle_runs_span_lines() {
+    assert_eq!(
+        Pattern::parse_rle("x = 12, y = 3\n12o$\n3$\n2bo!"),
+        Err(PatternError::OutOfBounds {
+            width: 12,
+            height: 3
+        })
+    );
+
+    let pattern = Pattern::parse_rle("x=12,y=3\n1\n2o2$2bo!").unwrap();
+    assert_eq!(pattern.rule, None);
+    assert_eq!(pattern.get(11, 0), Some(CellState::Alive));
+    assert_eq!(pattern.get(2, 2), Some(CellState::Alive));
+    assert_eq!(pattern.get(3, 2), Some(CellState::Dead));
+}
+
+#[test]
+fn invalid_rle_errors() {
+    assert_eq!(
+        Pattern::parse_rle("#N Nothing\n"),
+        Err(PatternError::MissingHeader)
+    );
+    assert!(matches!(
+        Pattern::parse_rle("x = 3\nooo!"),
+        Err(PatternError::InvalidHeader(_))
+    ));
+    assert!(matches!(
+        Pattern::parse_rle("x = 3, y = 1, rule = life\nooo!"),
+        Err(PatternError::Rule(_))
+    ));
+    assert_eq!(
+        Pattern::parse_rle("x = 3, y = 1\noxo!"),
+        Err(PatternError::UnexpectedCharacter {
+            character: 'x',
+            line: 2
+        })
+    );
+}
+
+#[test]
+fn oversized_patterns_error() {
+    assert_eq!(
+        Pattern::parse_rle("x = 4294967296, y = 4294967296\no!"),
+        Err(PatternError::TooLarge {
+            width: 1 << 32,
+            height: 1 << 32
+        })
+    );
+    assert_eq!(
+        Pattern::parse_rle("x = 100000, y = 100000\no!"),
+        Err(PatternError::TooLarge {
+            width: 100_000,
+            height: 100_000
+        })
+    );
+
+    // Runs too long for a usize, and runs which would overflow once added to the position, are errors
+    assert_eq!(
+        Pattern::parse_rle("x = 3, y = 3\n99999999999999999999999o!"),
+        Err(PatternError::RunTooLong { line: 2 })
+    );
+    assert_eq!(
+        Pattern::parse_rle("x = 3, y = 3\nb18446744073709551615bo!"),
+        Err(PatternError::OutOfBounds {
+            width: 3,
+            height: 3
+        })
+    );
+    assert_eq!(
+        Pattern::parse_rle("x = 3, y = 3\n$18446744073709551615$o!"),
+        Err(PatternError::OutOfBounds {
+            width: 3,
+            height: 3
+        })
+    );
+}
+
+#[test]
+fn plaintext_parses() {
+    let pattern = Pattern::parse_plaintext(GLIDER_PLAINTEXT).unwrap();
+
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(pattern.comments, ["The smallest spaceship."]);
+    assert_eq!(pattern.cells, glider_cells());
+
+    // Short rows are padded with dead cells
+    let pattern = Pattern::parse_plaintext(".O\nOOO\n").unwrap();
+    assert_eq!((pattern.width(), pattern.height()), (3, 2));
+    assert_eq!(pattern.get(2, 0), Some(CellState::Dead));
+}
+
+#[test]
+fn format_is_detected() {
+    assert_eq!(
+        Pattern::parse(GLIDER_RLE).unwrap().cells,
+        Pattern::parse(GLIDER_PLAINTEXT).unwrap().cells
+    );
+}
+
+#[test]
+fn patterns_round_trip() {
+    let pattern = Pattern::parse_rle(GLIDER_RLE).unwrap();
+
+    assert_eq!(Pattern::parse_rle(&pattern.to_rle()), Ok(pattern.clone()));
+    assert_eq!(
+        Pattern::parse_plaintext(&pattern.to_plaintext()),
+        Ok(Pattern {
+            rule: None,
+            ..pattern
+        })
+    );
+}
+
+#[test]
+fn multi_state_rle_round_trips() {
+    let rle = "x = 4, y = 2, rule = B2/S345/C4\n.AB$2C!\n";
+    let pattern = Pattern::parse_rle(rle).unwrap();
+
+    assert_eq!(pattern.rule, Some(Rule::STAR_WARS));
+    assert_eq!(
+        pattern.cells,
+        [
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Dying(1),
+            CellState::Dead,
+            CellState::Dying(2),
+            CellState::Dying(2),
+            CellState::Dead,
+            CellState::Dead,
+        ]
+    );
+    assert_eq!(pattern.to_rle(), rle);
+}
+
+#[test]
+fn rle_is_written_compactly() {
+    let pattern = Pattern::parse_plaintext("OOO.\n....\n....\n.O..\n").unwrap();
+
+    assert_eq!(pattern.to_rle(), "x = 4, y = 4\n3o3$bo!\n");
+}
+
+#[test]
+fn long_rle_lines_wrap() {
+    let pattern = Pattern::parse_plaintext(&".O".repeat(100)).unwrap();
+    let rle = pattern.to_rle();
+
+    assert!(rle.lines().all(|line| line.len() <= RLE_LINE_LENGTH));
+    assert_eq!(Pattern::parse_rle(&rle), Ok(pattern));
+}
+
+#[test]
+fn patterns_rotate_and_flip() {
+    let pattern = Pattern::parse_plaintext("OO.\n...\n").unwrap();
+
+    let oriented = |rotation, flipped| {
+        pattern
+            .oriented(Orientation { rotation, flipped })
+            .to_plaintext()
+    };
+
+    assert_eq!(oriented(Rotation::None, false), "OO.\n...\n");
+    assert_eq!(oriented(Rotation::Clockwise, false), ".O\n.O\n..\n");
+    assert_eq!(oriented(Rotation::Half, false), "...\n.OO\n");
+    assert_eq!(oriented(Rotation::Anticlockwise, false), "..\nO.\nO.\n");
+    assert_eq!(oriented(Rotation::None, true), ".OO\n...\n");
+    assert_eq!(oriented(Rotation::Clockwise, true), "..\n.O\n.O\n");
+}
+
+#[test]
+fn patterns_stamp_into_state() {
+    let mut state = State::empty(5, 5);
+    let glider = Pattern::parse_rle(GLIDER_RLE).unwrap();
+
+    glider.stamp_centered(&mut state);
+    assert_eq!(
+        Pattern::from(&state).to_plaintext(),
+        ".....\n..O..\n...O.\n.OOO.\n.....\n"
+    );
+
+    // Cells outside of the board are ignored
+    let mut state = State::empty(3, 3);
+    glider.stamp(
+        &mut state,
+        Coordinates { y: 1, x: -1 },
+        Orientation {
+            rotation: Rotation::Half,
+            flipped: false,
+        },
+    );
+    assert_eq!(Pattern::from(&state).to_plaintext(), "...\nOO.\n...\n");
+}
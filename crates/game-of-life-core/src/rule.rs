@@ -0,0 +1,350 @@
+use crate::state::CellState;
+use nom::{
+    branch::alt,
+    bytes::complete::tag_no_case,
+    character::complete::{char, one_of, u8 as count},
+    combinator::{all_consuming, consumed, opt, verify},
+    multi::{fold_many0, separated_list1},
+    sequence::{preceded, tuple},
+    IResult, Parser,
+};
+use std::{fmt, str::FromStr};
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// The largest number of alive neighbours a rule can count, which allows the larger neighbourhoods of Larger than
+/// Life rules.
+pub(crate) const MAX_NEIGHBOURS: usize = 127;
+
+/// The largest count written as a single digit, as in the `23` of `B3/S23`.
+const MAX_DIGIT_COUNT: usize = 8;
+
+/// A life-like rule, describing how many alive neighbours cause a cell to be born or to survive.
+///
+/// Rules are usually written as rulestrings in B/S notation, such as `B3/S23` for Conway's game of life. Rules
+/// from the Generations family add a number of states, such as `B2/S/C3` for Brian's Brain, where cells which
+/// don't survive pass through dying states before they are dead.
+///
+/// Neighbourhoods with more than eight cells can have larger counts, which are written as ranges such as
+/// `B34-45/S34-58`.
+///
+/// Some rules are known by name, such as Immigration and `QuadLife`, where alive cells belong to one of several
+/// competing species, and each newborn cell takes the species of most of its parents.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Rule {
+    /// Bit `n` is set when a dead cell with `n` alive neighbours is born
+    birth: u128,
+    /// Bit `n` is set when an alive cell with `n` alive neighbours survives
+    survival: u128,
+    /// The number of states a cell can have, including alive and dead
+    states: u8,
+    /// The number of species an alive cell can belong to, which is 1 for most rules
+    species: u8,
+}
+
+impl Rule {
+    /// Conway's game of life.
+    pub const CONWAY: Self = Self::from_masks(0b1000, 0b1100);
+    /// `B36/S23`, which is Conway's rule with a small replicator.
+    pub const HIGH_LIFE: Self = Self::from_masks(0b100_1000, 0b1100);
+    /// `B2/S`, where every alive cell dies each generation.
+    pub const SEEDS: Self = Self::from_masks(0b100, 0);
+    /// `B3678/S34678`, which is symmetric under inverting alive and dead cells.
+    pub const DAY_AND_NIGHT: Self = Self::from_masks(0b1_1100_1000, 0b1_1101_1000);
+    /// `B3/S12345`, which grows maze-like patterns.
+    pub const MAZE: Self = Self::from_masks(0b1000, 0b11_1110);
+    /// `B2/S/C3`, where every alive cell spends a generation dying before it can be born again.
+    pub const BRIANS_BRAIN: Self = Self::from_masks(0b100, 0).with_states(3);
+    /// `B2/S345/C4`, which grows long-lived gliders and walls.
+    pub const STAR_WARS: Self = Self::from_masks(0b100, 0b11_1000).with_states(4);
+    /// Conway's game of life with two species, where each newborn cell takes the species of most of its parents.
+    pub const IMMIGRATION: Self = Self::CONWAY.with_species(2);
+    /// Conway's game of life with four species, where each newborn cell takes the species of most of its parents, or
+    /// the species none of them have when all three differ.
+    pub const QUAD_LIFE: Self = Self::CONWAY.with_species(4);
+
+    const fn from_masks(birth: u128, survival: u128) -> Self {
+        Self {
+            birth,
+            survival,
+            states: 2,
+            species: 1,
+        }
+    }
+
+    const fn with_species(self, species: u8) -> Self {
+        Self { species, ..self }
+    }
+
+    /// Creates a rule from the neighbour counts that cause a birth and the counts that allow survival.
+    ///
+    /// # Panics
+    /// When a count is greater than 127, as no neighbourhood has that many cells.
+    pub fn new(
+        birth: impl IntoIterator<Item = usize>,
+        survival: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        let to_mask = |counts: &mut dyn Iterator<Item = usize>| {
+            counts.fold(0, |mask, count| {
+                assert!(
+                    count <= MAX_NEIGHBOURS,
+                    "a cell cannot have {count} neighbours"
+                );
+                mask | 1 << count
+            })
+        };
+
+        Self::from_masks(
+            to_mask(&mut birth.into_iter()),
+            to_mask(&mut survival.into_iter()),
+        )
+    }
+
+    /// Sets the number of states a cell can have, where states beyond alive and dead are stages of dying.
+    ///
+    /// # Panics
+    /// When there are fewer than two states.
+    #[must_use]
+    pub const fn with_states(self, states: u8) -> Self {
+        assert!(states >= 2, "a cell must be able to be alive or dead");
+        Self { states, ..self }
+    }
+
+    /// Gets the number of states a cell can have, which is 2 for life-like rules.
+    #[must_use]
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// Gets the number of species an alive cell can belong to, which is 1 except for rules such as Immigration.
+    #[must_use]
+    pub fn species(&self) -> u8 {
+        self.species
+    }
+
+    /// Gets the species of a newborn cell from the species of its alive neighbours, which is the most common species
+    /// among them, or the only species none of them have when they all differ.
+    #[must_use]
+    pub fn newborn_species(&self, parents: impl IntoIterator<Item = u8>) -> u8 {
+        let mut counts = vec![0_usize; usize::from(self.species)];
+        let mut parent_count = 0;
+        for species in parents {
+            if let Some(count) = counts.get_mut(usize::from(species)) {
+                *count += 1;
+                parent_count += 1;
+            }
+        }
+
+        let most = counts.iter().copied().max().unwrap_or(0);
+        let species = if most == 1 && parent_count + 1 == counts.len() {
+            counts.iter().position(|&count| count == 0)
+        } else {
+            counts.iter().position(|&count| count == most)
+        };
+
+        species
+            .and_then(|species| u8::try_from(species).ok())
+            .unwrap_or(0)
+    }
+
+    #[must_use]
+    pub fn is_born(&self, alive_neighbours: usize) -> bool {
+        alive_neighbours <= MAX_NEIGHBOURS && self.birth & 1 << alive_neighbours != 0
+    }
+
+    #[must_use]
+    pub fn survives(&self, alive_neighbours: usize) -> bool {
+        alive_neighbours <= MAX_NEIGHBOURS && self.survival & 1 << alive_neighbours != 0
+    }
+
+    /// Gets the state a cell will have in the next generation.
+    #[must_use]
+    pub fn next_state(&self, state: CellState, alive_neighbours: usize) -> CellState {
+        match state {
+            CellState::Alive if self.survives(alive_neighbours) => CellState::Alive,
+            CellState::Alive => self.decay(0),
+            CellState::Dying(dying_stage) => self.decay(dying_stage),
+            CellState::Dead => CellState::from(self.is_born(alive_neighbours)),
+        }
+    }
+
+    /// Gets the state after a cell at the given stage of dying, where alive cells are at stage 0.
+    ///
+    /// Cells at a stage the rule doesn't have, such as from a board made under another rule, are dead next.
+    fn decay(self, stage: u8) -> CellState {
+        if stage.checked_add(2).is_some_and(|next| next < self.states) {
+            CellState::Dying(stage + 1)
+        } else {
+            CellState::Dead
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::CONWAY
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Rules with species have no rulestring, so they are only known by name
+        match *self {
+            Self::IMMIGRATION => return f.write_str("Immigration"),
+            Self::QUAD_LIFE => return f.write_str("QuadLife"),
+            _ => {}
+        }
+
+        let counts = |mask: u128| {
+            let counts = (0..=MAX_NEIGHBOURS).filter(|count| mask & 1 << count != 0);
+
+            if mask >> (MAX_DIGIT_COUNT + 1) == 0 {
+                return counts.map(|count| count.to_string()).collect::<String>();
+            }
+
+            // Larger counts are written as ranges of consecutive counts
+            let mut ranges = Vec::<(usize, usize)>::new();
+            for count in counts {
+                match ranges.last_mut() {
+                    Some((_, end)) if *end + 1 == count => *end = count,
+                    _ => ranges.push((count, count)),
+                }
+            }
+
+            match ranges[..] {
+                [(start, end)] => format!("{start}-{end}"),
+                _ => ranges
+                    .into_iter()
+                    .map(|(start, end)| {
+                        if start == end {
+                            start.to_string()
+                        } else {
+                            format!("{start}-{end}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(","),
+            }
+        };
+
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))?;
+
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("invalid rulestring {0:?}, expected B/S notation such as B3/S23 or B2/S/C3, or Immigration or QuadLife")]
+pub struct ParseRuleError(String);
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        all_consuming(alt((named_rule, rulestring)))(input.trim())
+            .map(|(_, rule)| rule)
+            .map_err(|_| ParseRuleError(input.to_string()))
+    }
+}
+
+impl TryFrom<String> for Rule {
+    type Error = ParseRuleError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+impl From<Rule> for String {
+    fn from(rule: Rule) -> Self {
+        rule.to_string()
+    }
+}
+
+/// Parses a list of neighbour counts, such as the `23` in `B3/S23` or the `34-45` in `B34-45/S34-58`, into a bit
+/// mask.
+fn neighbour_counts(input: &str) -> IResult<&str, u128> {
+    alt((count_ranges, count_digits))(input)
+}
+
+/// Parses a list of single digit counts, such as the `23` in `B3/S23`.
+fn count_digits(input: &str) -> IResult<&str, u128> {
+    fold_many0(
+        one_of("012345678"),
+        || 0,
+        |mask, digit| mask | 1 << digit.to_digit(10).unwrap(),
+    )(input)
+}
+
+/// Parses a list of ranges of counts separated by commas, such as the `3-5,8` in `B3-5,8/S2`.
+///
+/// A list without a range or a comma is a list of single digit counts instead.
+fn count_ranges(input: &str) -> IResult<&str, u128> {
+    let range = tuple((count, opt(preceded(char('-'), count))))
+        .map(|(start, end)| (start, end.unwrap_or(start)));
+
+    verify(
+        consumed(separated_list1(char(','), range)),
+        |(text, ranges): &(&str, Vec<(u8, u8)>)| {
+            text.contains([',', '-'])
+                && ranges
+                    .iter()
+                    .all(|&(start, end)| start <= end && usize::from(end) <= MAX_NEIGHBOURS)
+        },
+    )
+    .map(|(_, ranges)| {
+        ranges.into_iter().fold(0, |mask, (start, end)| {
+            (start..=end).fold(mask, |mask, count| mask | 1 << count)
+        })
+    })
+    .parse(input)
+}
+
+/// Parses the number of states, such as the `3` in `B2/S/C3`, which must be at least 2.
+fn states(input: &str) -> IResult<&str, u8> {
+    verify(count, |&states| states >= 2)(input)
+}
+
+/// Parses the name of a rule which has no rulestring, such as `Immigration`.
+fn named_rule(input: &str) -> IResult<&str, Rule> {
+    alt((
+        tag_no_case("Immigration").map(|_| Rule::IMMIGRATION),
+        tag_no_case("QuadLife").map(|_| Rule::QUAD_LIFE),
+    ))(input)
+}
+
+fn rulestring(input: &str) -> IResult<&str, Rule> {
+    let birth = || preceded(one_of("Bb"), neighbour_counts);
+    let survival = || preceded(one_of("Ss"), neighbour_counts);
+    let generations = || opt(preceded(char('/'), preceded(one_of("Cc"), states)));
+
+    alt((
+        // B3/S23 or B2/S/C3
+        tuple((birth(), preceded(char('/'), survival()), generations())),
+        // S23/B3 or S/B2/C3
+        tuple((survival(), preceded(char('/'), birth()), generations()))
+            .map(|(survival, birth, states)| (birth, survival, states)),
+        // 23/3 or /2/3, the older S/B notation
+        tuple((
+            neighbour_counts,
+            preceded(char('/'), neighbour_counts),
+            opt(preceded(char('/'), states)),
+        ))
+        .map(|(survival, birth, states)| (birth, survival, states)),
+    ))
+    .map(|(birth, survival, states)| {
+        Rule::from_masks(birth, survival).with_states(states.unwrap_or(2))
+    })
+    .parse(input)
+}
@@ -0,0 +1,140 @@
+use super::*;
+
+#[test]
+fn well_known_rules_parse() {
+    assert_eq!("B3/S23".parse(), Ok(Rule::CONWAY));
+    assert_eq!("B36/S23".parse(), Ok(Rule::HIGH_LIFE));
+    assert_eq!("B2/S".parse(), Ok(Rule::SEEDS));
+    assert_eq!("B3678/S34678".parse(), Ok(Rule::DAY_AND_NIGHT));
+    assert_eq!("B3/S12345".parse(), Ok(Rule::MAZE));
+}
+
+#[test]
+fn alternative_notations_parse() {
+    assert_eq!("b3/s23".parse(), Ok(Rule::CONWAY));
+    assert_eq!("S23/B3".parse(), Ok(Rule::CONWAY));
+    assert_eq!("23/36".parse(), Ok(Rule::HIGH_LIFE));
+    assert_eq!("/2".parse(), Ok(Rule::SEEDS));
+    assert_eq!("S/B2/C3".parse(), Ok(Rule::BRIANS_BRAIN));
+    assert_eq!("345/2/4".parse(), Ok(Rule::STAR_WARS));
+}
+
+#[test]
+fn generations_rules_parse() {
+    assert_eq!("B2/S/C3".parse(), Ok(Rule::BRIANS_BRAIN));
+    assert_eq!("b2/s345/c4".parse(), Ok(Rule::STAR_WARS));
+    assert_eq!("B3/S23/C2".parse(), Ok(Rule::CONWAY));
+    assert_eq!(Rule::STAR_WARS.states(), 4);
+}
+
+#[test]
+fn rules_with_species_parse_by_name() {
+    assert_eq!("Immigration".parse(), Ok(Rule::IMMIGRATION));
+    assert_eq!("quadlife".parse(), Ok(Rule::QUAD_LIFE));
+    assert_eq!(Rule::QUAD_LIFE.species(), 4);
+    assert_eq!(Rule::CONWAY.species(), 1);
+
+    assert_eq!(Rule::IMMIGRATION.to_string(), "Immigration");
+    assert_eq!(Rule::QUAD_LIFE.to_string(), "QuadLife");
+    assert_ne!(Rule::IMMIGRATION, Rule::CONWAY);
+}
+
+#[test]
+fn newborns_take_the_species_of_most_parents() {
+    assert_eq!(Rule::IMMIGRATION.newborn_species([1, 0, 1]), 1);
+    assert_eq!(Rule::IMMIGRATION.newborn_species([0, 0, 1]), 0);
+    assert_eq!(Rule::QUAD_LIFE.newborn_species([3, 2, 3]), 3);
+
+    // When every parent differs, the newborn takes the species none of them have
+    assert_eq!(Rule::QUAD_LIFE.newborn_species([0, 3, 1]), 2);
+
+    assert_eq!(Rule::CONWAY.newborn_species([0, 0, 0]), 0);
+}
+
+#[test]
+fn invalid_rules_error() {
+    for input in [
+        "", "B3", "B9/S23", "B3/S23/", "B3/X23", "life", "B2/S/C1", "B2/S/C",
+    ] {
+        assert!(input.parse::<Rule>().is_err(), "{input:?} should not parse");
+    }
+}
+
+#[test]
+fn rules_display_as_rulestrings() {
+    for rule in [
+        Rule::CONWAY,
+        Rule::HIGH_LIFE,
+        Rule::SEEDS,
+        Rule::DAY_AND_NIGHT,
+        Rule::MAZE,
+        Rule::BRIANS_BRAIN,
+        Rule::STAR_WARS,
+    ] {
+        assert_eq!(rule.to_string().parse(), Ok(rule));
+    }
+
+    assert_eq!(Rule::DAY_AND_NIGHT.to_string(), "B3678/S34678");
+    assert_eq!(Rule::STAR_WARS.to_string(), "B2/S345/C4");
+}
+
+#[test]
+fn rules_can_be_built_from_counts() {
+    assert_eq!(Rule::new([3, 6], [2, 3]), Rule::HIGH_LIFE);
+    assert_eq!(Rule::new([2], []), Rule::SEEDS);
+}
+
+#[test]
+fn conway_transitions() {
+    let rule = Rule::CONWAY;
+
+    assert_eq!(rule.next_state(CellState::Dead, 3), CellState::Alive);
+    assert_eq!(rule.next_state(CellState::Dead, 2), CellState::Dead);
+    assert_eq!(rule.next_state(CellState::Alive, 2), CellState::Alive);
+    assert_eq!(rule.next_state(CellState::Alive, 1), CellState::Dead);
+    assert_eq!(rule.next_state(CellState::Alive, 4), CellState::Dead);
+}
+
+#[test]
+fn generations_cells_decay() {
+    let rule = Rule::STAR_WARS;
+
+    assert_eq!(rule.next_state(CellState::Alive, 4), CellState::Alive);
+    assert_eq!(rule.next_state(CellState::Alive, 2), CellState::Dying(1));
+    assert_eq!(rule.next_state(CellState::Dying(1), 2), CellState::Dying(2));
+    assert_eq!(rule.next_state(CellState::Dying(2), 2), CellState::Dead);
+    assert_eq!(rule.next_state(CellState::Dead, 2), CellState::Alive);
+}
+
+#[test]
+fn the_last_dying_stages_die() {
+    let rule = Rule::CONWAY.with_states(255);
+
+    assert_eq!(
+        rule.next_state(CellState::Dying(252), 0),
+        CellState::Dying(253)
+    );
+    assert_eq!(rule.next_state(CellState::Dying(253), 0), CellState::Dead);
+    assert_eq!(rule.next_state(CellState::Dying(254), 0), CellState::Dead);
+    assert_eq!(rule.next_state(CellState::Dying(255), 0), CellState::Dead);
+    assert_eq!(
+        Rule::BRIANS_BRAIN.next_state(CellState::Dying(200), 2),
+        CellState::Dead
+    );
+}
+
+#[test]
+fn larger_counts_are_written_as_ranges() {
+    let rule = "B34-45/S34-58".parse::<Rule>().unwrap();
+
+    assert!(rule.is_born(34) && rule.is_born(45) && !rule.is_born(46));
+    assert!(rule.survives(58) && !rule.survives(33));
+    assert_eq!(rule.to_string(), "B34-45/S34-58");
+
+    let rule = Rule::new([2, 3, 10, 12, 13], [12]);
+    assert_eq!(rule.to_string(), "B2-3,10,12-13/S12-12");
+    assert_eq!(rule.to_string().parse(), Ok(rule));
+
+    assert!("B3-2/S".parse::<Rule>().is_err());
+    assert!("B3-128/S".parse::<Rule>().is_err());
+}
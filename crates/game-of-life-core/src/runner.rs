@@ -0,0 +1,332 @@
+use crate::{
+    pattern::{Orientation, Pattern},
+    state::{CellState, Frame, FrameKind, State},
+    Coordinates,
+};
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Stream, StreamExt,
+};
+use futures_timer::Delay;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A change to how a [`Runner`] is running, sent through a [`Controller`] or applied with [`Runner::apply`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Command {
+    Pause,
+    Resume,
+    TogglePause,
+    /// Steps forward a generation straight away, which is mostly useful while paused.
+    Step,
+    /// Steps back a generation, which needs the state to have a history.
+    StepBack,
+    /// Sets how many times faster than its delay the runner steps, where speeds which are not positive, or so slow
+    /// that the time between steps doesn't fit in a [`Duration`], are ignored.
+    SetSpeed(f64),
+    /// Sets the state of the cell at an index.
+    Edit(usize, CellState),
+    /// Kills every cell, as with [`State::clear`].
+    Clear,
+    /// Refills the board from a seed and a density, as with [`State::randomise`], where densities which are not
+    /// between 0 and 1 are ignored.
+    Randomise(u64, f64),
+    /// Ends the stream.
+    Stop,
+}
+
+/// Sends commands to a [`Runner`], which can be cloned and moved into event handlers.
+///
+/// Commands sent after the runner has been dropped are ignored.
+#[derive(Clone, Debug)]
+pub struct Controller {
+    sender: UnboundedSender<Command>,
+}
+
+impl Controller {
+    pub fn send(&self, command: Command) {
+        // The runner is only gone once nothing is listening, so there is nobody to tell
+        let _ = self.sender.unbounded_send(command);
+    }
+
+    pub fn pause(&self) {
+        self.send(Command::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.send(Command::Resume);
+    }
+
+    pub fn toggle_pause(&self) {
+        self.send(Command::TogglePause);
+    }
+
+    pub fn step(&self) {
+        self.send(Command::Step);
+    }
+
+    pub fn step_back(&self) {
+        self.send(Command::StepBack);
+    }
+
+    pub fn set_speed(&self, speed: f64) {
+        self.send(Command::SetSpeed(speed));
+    }
+
+    pub fn edit(&self, index: usize, state: CellState) {
+        self.send(Command::Edit(index, state));
+    }
+
+    pub fn clear(&self) {
+        self.send(Command::Clear);
+    }
+
+    pub fn randomise(&self, seed: u64, density: f64) {
+        self.send(Command::Randomise(seed, density));
+    }
+
+    pub fn stop(&self) {
+        self.send(Command::Stop);
+    }
+}
+
+/// Steps a state on a timer, as a stream of the frames to render.
+///
+/// The first frame is yielded straight away, and the rest once every delay, divided by the speed. Commands from a
+/// [`Controller`] are applied as soon as they arrive, where stepping, stepping back, editing, clearing and
+/// randomising yield a frame straight away, even while paused.
+pub struct Runner {
+    state: State,
+    delay: Duration,
+    speed: f64,
+    paused: bool,
+    frame_kind: FrameKind,
+    /// The timer for the next step, or `None` when the next step is due straight away
+    timer: Option<Delay>,
+    /// Frames to yield before the next step, such as after stepping back
+    pending: VecDeque<Frame>,
+    is_stopped: bool,
+    commands: UnboundedReceiver<Command>,
+    sender: UnboundedSender<Command>,
+}
+
+impl Runner {
+    /// Creates a runner which steps the state once every `delay`.
+    #[must_use]
+    pub fn new(state: State, delay: Duration) -> Self {
+        let (sender, commands) = mpsc::unbounded();
+
+        Self {
+            state,
+            delay,
+            speed: 1.0,
+            paused: false,
+            frame_kind: FrameKind::default(),
+            timer: None,
+            pending: VecDeque::new(),
+            is_stopped: false,
+            commands,
+            sender,
+        }
+    }
+
+    /// Starts the runner paused, so nothing is stepped until it is resumed or stepped.
+    #[must_use]
+    pub fn paused(self) -> Self {
+        Self {
+            paused: true,
+            ..self
+        }
+    }
+
+    /// Sets the kind of frame yielded by each step, which defaults to frames holding every cell.
+    #[must_use]
+    pub fn with_frame_kind(self, frame_kind: FrameKind) -> Self {
+        Self { frame_kind, ..self }
+    }
+
+    /// Sets the kind of frame yielded by later steps, such as once a backend has drawn the whole board.
+    pub fn set_frame_kind(&mut self, frame_kind: FrameKind) {
+        self.frame_kind = frame_kind;
+    }
+
+    /// Creates a controller which sends commands to this runner.
+    #[must_use]
+    pub fn controller(&self) -> Controller {
+        Controller {
+            sender: self.sender.clone(),
+        }
+    }
+
+    /// Gets the state being run, such as to check whether it has settled.
+    #[must_use]
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Gets the state being run, which can be changed between frames.
+    ///
+    /// Edits made here are not rendered until the next frame, so [`Command::Edit`] is usually more useful.
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    /// Takes back the state, such as to save it once the runner is finished.
+    #[must_use]
+    pub fn into_state(self) -> State {
+        self.state
+    }
+
+    /// Stamps a pattern into the board with its top left corner at some coordinates, as with [`Pattern::stamp`], and
+    /// renders the cells it covers.
+    pub fn stamp(&mut self, pattern: &Pattern, at: Coordinates) {
+        pattern.stamp(&mut self.state, at, Orientation::default());
+
+        let frame = match self.frame_kind {
+            FrameKind::Full => self.state.frame(),
+            FrameKind::Diff => {
+                let (width, height) = (self.state.width(), self.state.height());
+                let columns = clamped(at.x, pattern.width(), width);
+                let rows = clamped(at.y, pattern.height(), height);
+                self.state
+                    .frame_of(rows.flat_map(|y| columns.clone().map(move |x| y * width + x)))
+            }
+        };
+        self.pending.push_back(frame);
+    }
+
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    #[must_use]
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Gets the time between steps at the current speed.
+    #[must_use]
+    pub fn interval(&self) -> Duration {
+        // Speeds are only set when their interval fits, so this only falls back for the delay itself
+        self.interval_at(self.speed).unwrap_or(self.delay)
+    }
+
+    /// Gets the time between steps at a speed, which is `None` when it is too long to fit in a [`Duration`].
+    fn interval_at(&self, speed: f64) -> Option<Duration> {
+        Duration::try_from_secs_f64(self.delay.as_secs_f64() / speed).ok()
+    }
+
+    /// Applies a command straight away, as if it had been sent through a [`Controller`].
+    pub fn apply(&mut self, command: Command) {
+        match command {
+            Command::Resume => self.resume(),
+            Command::TogglePause if self.paused => self.resume(),
+            Command::Pause | Command::TogglePause => self.paused = true,
+            Command::Step => {
+                let frame = self.state.next_frame(self.frame_kind);
+                self.pending.push_back(frame);
+            }
+            Command::StepBack => {
+                if self.state.step_back() {
+                    self.pending.push_back(self.state.frame());
+                }
+            }
+            Command::SetSpeed(speed)
+                if speed > 0.0 && speed.is_finite() && self.interval_at(speed).is_some() =>
+            {
+                self.speed = speed;
+
+                // The next step is rescheduled, so slowing down from a very fast speed takes effect straight away
+                let interval = self.interval();
+                if let Some(timer) = &mut self.timer {
+                    timer.reset(interval);
+                }
+            }
+            Command::Edit(index, state) => {
+                if self.state.replace_at_index(index, state).is_some() {
+                    // Only the edited cell needs to be redrawn, unless whole frames have been asked for
+                    let frame = match self.frame_kind {
+                        FrameKind::Full => self.state.frame(),
+                        FrameKind::Diff => self.state.frame_of([index]),
+                    };
+                    self.pending.push_back(frame);
+                }
+            }
+            Command::Clear => {
+                self.state.clear();
+                self.pending.push_back(self.state.frame());
+            }
+            Command::Randomise(seed, density) if (0.0..=1.0).contains(&density) => {
+                self.state.randomise(seed, density);
+                self.pending.push_back(self.state.frame());
+            }
+            Command::Stop => self.is_stopped = true,
+            // Invalid speeds and densities are ignored
+            Command::SetSpeed(_) | Command::Randomise(..) => {}
+        }
+    }
+
+    fn resume(&mut self) {
+        if self.paused {
+            self.paused = false;
+            self.timer = Some(Delay::new(self.interval()));
+        }
+    }
+}
+
+impl Stream for Runner {
+    type Item = Frame;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let runner = self.get_mut();
+
+        // The runner keeps its own sender, so the channel never closes
+        while let Poll::Ready(Some(command)) = runner.commands.poll_next_unpin(cx) {
+            runner.apply(command);
+        }
+
+        if runner.is_stopped {
+            return Poll::Ready(None);
+        }
+
+        if let Some(frame) = runner.pending.pop_front() {
+            return Poll::Ready(Some(frame));
+        }
+
+        if runner.paused {
+            return Poll::Pending;
+        }
+
+        if let Some(timer) = &mut runner.timer {
+            if Pin::new(timer).poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+
+        let interval = runner.interval();
+        match &mut runner.timer {
+            Some(timer) => timer.reset(interval),
+            None => runner.timer = Some(Delay::new(interval)),
+        }
+
+        Poll::Ready(Some(runner.state.next_frame(runner.frame_kind)))
+    }
+}
+
+/// Gets the part of a span of cells starting at `start` which is within a board of some length.
+fn clamped(start: i32, length: usize, board: usize) -> std::ops::Range<usize> {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let end = start.saturating_add(length as i32);
+    let [start, end] =
+        [start, end].map(|position| usize::try_from(position).unwrap_or(0).min(board));
+    start..end
+}
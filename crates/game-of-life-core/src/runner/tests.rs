@@ -0,0 +1,193 @@
+use super::*;
+use crate::{boundary::Boundary, Coordinates};
+use futures::{executor::block_on, FutureExt};
+use std::time::Instant;
+
+fn blinker() -> State {
+    let mut state = State::empty(5, 5)
+        .with_boundary(Boundary::Dead)
+        .with_history(1 << 10);
+    for index in [11, 12, 13] {
+        state.replace_at_index(index, CellState::Alive);
+    }
+    state
+}
+
+/// Gets the next frame if one is ready without waiting.
+fn ready_frame(runner: &mut Runner) -> Option<Frame> {
+    runner.next().now_or_never().flatten()
+}
+
+#[test]
+fn runners_step_on_a_timer() {
+    let mut runner = Runner::new(blinker(), Duration::from_millis(20));
+    let start = Instant::now();
+
+    block_on(async {
+        for _ in 0..4 {
+            runner.next().await.unwrap();
+        }
+    });
+
+    // The first frame is straight away, and each later frame waits for the delay
+    assert!(start.elapsed() >= Duration::from_millis(60));
+    assert_eq!(runner.state().generation(), 4);
+}
+
+#[test]
+fn faster_runners_step_sooner() {
+    let mut runner = Runner::new(blinker(), Duration::from_secs(30));
+    runner.controller().set_speed(3000.0);
+    let start = Instant::now();
+
+    block_on(async {
+        for _ in 0..3 {
+            runner.next().await.unwrap();
+        }
+    });
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(runner.interval(), Duration::from_millis(10));
+}
+
+#[test]
+fn invalid_speeds_are_ignored() {
+    let mut runner = Runner::new(blinker(), Duration::from_millis(10));
+    for speed in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        runner.apply(Command::SetSpeed(speed));
+    }
+
+    assert!((runner.speed() - 1.0).abs() < f64::EPSILON);
+}
+
+#[test]
+fn speeds_too_slow_for_a_duration_are_ignored() {
+    let mut runner = Runner::new(blinker(), Duration::from_millis(10));
+    runner.apply(Command::SetSpeed(1e-30));
+    assert!((runner.speed() - 1.0).abs() < f64::EPSILON);
+
+    // Halving the speed, as the frontends do, stops once the time between steps no longer fits
+    for _ in 0..100 {
+        runner.apply(Command::SetSpeed(runner.speed() / 2.0));
+        assert!(runner.interval() >= Duration::from_millis(10));
+    }
+    assert!(runner.interval() > Duration::from_secs(1 << 40));
+}
+
+#[test]
+fn paused_runners_only_step_when_asked() {
+    let mut runner = Runner::new(blinker(), Duration::from_millis(1)).paused();
+    let controller = runner.controller();
+
+    assert!(ready_frame(&mut runner).is_none());
+
+    controller.step();
+    controller.step();
+    assert!(ready_frame(&mut runner).is_some());
+    assert!(ready_frame(&mut runner).is_some());
+    assert!(ready_frame(&mut runner).is_none());
+    assert_eq!(runner.state().generation(), 2);
+
+    controller.step_back();
+    let frame = ready_frame(&mut runner).unwrap();
+    assert_eq!(frame.kind(), FrameKind::Full);
+    assert_eq!(runner.state().generation(), 1);
+}
+
+#[test]
+fn edits_are_rendered_straight_away() {
+    let mut runner = Runner::new(blinker(), Duration::from_millis(1)).paused();
+    runner.controller().edit(0, CellState::Alive);
+
+    let frame = ready_frame(&mut runner).unwrap();
+    assert_eq!(frame.to_state_iter().next(), Some(CellState::Alive));
+    assert_eq!(runner.state().generation(), 0);
+
+    // Edits beyond the board are ignored
+    runner.controller().edit(25, CellState::Alive);
+    assert!(ready_frame(&mut runner).is_none());
+}
+
+#[test]
+fn diff_runners_only_redraw_edited_cells() {
+    let mut runner = Runner::new(blinker(), Duration::from_millis(1))
+        .with_frame_kind(FrameKind::Diff)
+        .paused();
+    runner.controller().edit(6, CellState::Alive);
+
+    let frame = ready_frame(&mut runner).unwrap();
+    assert_eq!(frame.kind(), FrameKind::Diff);
+    let cells = frame.into_iter().collect::<Vec<_>>();
+    assert_eq!(cells.len(), 1);
+    assert_eq!(cells[0].coordinates, Coordinates { x: 1, y: 1 });
+    assert_eq!(cells[0].state, CellState::Alive);
+}
+
+#[test]
+fn boards_can_be_cleared_and_randomised() {
+    let mut runner = Runner::new(blinker(), Duration::from_millis(1)).paused();
+    let controller = runner.controller();
+
+    controller.step();
+    ready_frame(&mut runner).unwrap();
+    let before = runner.state().cells();
+
+    controller.clear();
+    ready_frame(&mut runner).unwrap();
+    assert_eq!(runner.state().population(), 0);
+
+    controller.randomise(1, 0.5);
+    ready_frame(&mut runner).unwrap();
+    assert_eq!(
+        runner.state().cells(),
+        State::from_seed(5, 5, 1, 0.5).cells()
+    );
+
+    // Densities beyond 0 and 1 are ignored
+    controller.randomise(1, 2.0);
+    assert!(ready_frame(&mut runner).is_none());
+
+    // Clearing and randomising are edits, so both are undone along with the next step
+    controller.step();
+    controller.step_back();
+    ready_frame(&mut runner).unwrap();
+    ready_frame(&mut runner).unwrap();
+    assert_eq!(runner.state().cells(), before);
+}
+
+#[test]
+fn resuming_continues_stepping() {
+    let mut runner = Runner::new(blinker(), Duration::from_millis(1)).paused();
+    runner.controller().toggle_pause();
+
+    block_on(async {
+        runner.next().await.unwrap();
+        runner.next().await.unwrap();
+    });
+
+    assert!(!runner.is_paused());
+    assert_eq!(runner.state().generation(), 2);
+}
+
+#[test]
+fn stopping_ends_the_stream() {
+    let mut runner = Runner::new(blinker(), Duration::from_millis(1));
+    runner.controller().stop();
+
+    assert!(block_on(runner.next()).is_none());
+}
+
+#[test]
+fn patterns_are_stamped_and_rendered() {
+    let mut runner = Runner::new(State::empty(5, 5), Duration::from_millis(1))
+        .paused()
+        .with_frame_kind(FrameKind::Diff);
+    let glider = Pattern::parse_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+
+    // Only the cells of the pattern within the board are stamped and redrawn
+    runner.stamp(&glider, Coordinates { y: 3, x: -1 });
+    let frame = ready_frame(&mut runner).unwrap();
+    assert_eq!(frame.into_iter().count(), 4);
+    assert_eq!(runner.state().population(), 2);
+    assert_eq!(runner.state().at_index(4 * 5 + 1), Some(CellState::Alive));
+}
@@ -1,18 +1,45 @@
-use crate::{CellRenderInfo, Coordinates};
-use rand::{distributions::Standard, rngs::SmallRng, Rng, SeedableRng};
-use std::ops::Not;
+use crate::{
+    boundary::Boundary,
+    neighbourhood::{self, Moore, Neighbourhood},
+    rule::Rule,
+    CellRenderInfo, Coordinates,
+};
+use chunked::ChunkedCells;
+use cycle::CycleDetector;
+use history::{Change, History};
+use packed::PackedCells;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+pub use snapshot::SnapshotError;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Not,
+    sync::Arc,
+};
 
 #[cfg(not(target_arch = "wasm32"))]
 use rayon::prelude::*;
 
+mod chunked;
+mod cycle;
+mod history;
+mod packed;
+mod snapshot;
 #[cfg(test)]
 mod tests;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(clippy::module_name_repetitions)]
 pub enum CellState {
-    Alive = 1,
-    Dead = 0,
+    Alive,
+    /// A cell which stopped being alive, and is passing through the refractory states of a rule with more than two
+    /// states, starting from stage 1.
+    ///
+    /// Dying cells are not counted as alive neighbours, and can't be born until they are dead.
+    Dying(u8),
+    Dead,
 }
 
 impl From<bool> for CellState {
@@ -31,41 +58,610 @@ impl Not for CellState {
     fn not(self) -> Self::Output {
         match self {
             Self::Alive => Self::Dead,
-            Self::Dead => Self::Alive,
+            Self::Dying(_) | Self::Dead => Self::Alive,
         }
     }
 }
 
+/// How the cells of a board are stored, which decides how they are stepped.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Storage {
+    /// One byte per cell, where neighbours are counted one cell at a time.
+    #[default]
+    Bytes,
+    /// One bit per cell, where neighbours are counted 64 cells at a time with bitwise adders, which is much faster
+    /// for large boards.
+    ///
+    /// A bit can't hold a dying cell, and the adders only count the eight surrounding cells, so boards with rules that
+    /// have more than two states or with other neighbourhoods are always stored as bytes.
+    Packed,
+    /// Chunks of an unbounded plane, which grows as patterns spread, so nothing ever collides with itself by wrapping
+    /// around. The board is a viewport onto the plane, which can be moved with [`State::set_viewport`].
+    ///
+    /// The boundary is ignored, and earlier generations can't be remembered, as changes beyond the viewport have no
+    /// index. Boards with rules where cells are born with no alive neighbours would fill the whole plane, so they
+    /// are always stored as bytes.
+    Unbounded,
+}
+
+enum Cells {
+    Bytes(Vec<CellState>),
+    Packed(PackedCells),
+    Unbounded(ChunkedCells),
+}
+
 pub struct State {
-    cells: Vec<CellState>,
+    cells: Cells,
     width: usize,
     height: usize,
+    rule: Rule,
+    boundary: Boundary,
+    neighbourhood: Arc<dyn Neighbourhood>,
+    /// The cells that changed in the last step, so only their neighbourhoods need to be stepped, or `None` when
+    /// every cell must be stepped
+    active: Option<Vec<usize>>,
+    /// The cells that were edited since the last step
+    edited: Vec<usize>,
+    generation: u64,
+    cycles: CycleDetector,
+    /// The period of the cycle the board has settled into, if it has been found
+    period: Option<u64>,
+    history: Option<History>,
+    /// The position on the plane of the top left cell, which only matters when the board is unbounded
+    viewport: Coordinates<i64>,
+    /// The species of each cell under a rule with more than one species, which dead cells keep until they are born
+    /// again
+    species: Option<Vec<u8>>,
+}
+
+/// Whether a board is still changing, or has died out or settled into a cycle.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Status {
+    /// The board has not yet been seen to repeat.
+    Running,
+    /// Every cell is dead.
+    Extinct,
+    /// The board no longer changes.
+    StillLife,
+    /// The board repeats itself every `period` generations.
+    Oscillator { period: u64 },
+}
+
+impl Status {
+    /// Whether the board will never show anything new, so a frontend can stop early.
+    #[must_use]
+    pub fn is_settled(self) -> bool {
+        self != Self::Running
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Running => f.write_str("running"),
+            Self::Extinct => f.write_str("extinct"),
+            Self::StillLife => f.write_str("still life"),
+            Self::Oscillator { period } => write!(f, "period {period} oscillator"),
+        }
+    }
+}
+
+/// The smallest rectangle holding every alive cell.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BoundingBox {
+    pub top_left: Coordinates,
+    pub width: usize,
+    pub height: usize,
 }
 
+/// Whether a frame holds every cell of the board, or only the cells that changed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameKind {
+    /// Every cell, where cells that changed since the previous frame are marked as needing to be rendered.
+    #[default]
+    Full,
+    /// Only the cells that changed since the previous frame, which is much smaller when most of the board is
+    /// still.
+    Diff,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Frame {
     buffer: Vec<CellRenderInfo>,
+    kind: FrameKind,
 }
 
 impl State {
+    /// Creates a randomly filled board, where half of the cells are alive.
+    #[must_use]
     pub fn new(width: usize, height: usize) -> Self {
-        let length = width * height;
+        Self::from_seed(width, height, rand::random(), 0.5)
+    }
 
-        let iter = SmallRng::from_entropy()
-            .sample_iter::<bool, _>(Standard)
-            .take(length);
+    /// Creates a randomly filled board, where each cell is alive with a probability of `density`.
+    ///
+    /// The same seed always creates the same board, so a run can be shared and reproduced from its seed.
+    ///
+    /// # Panics
+    /// When `density` is not between 0 and 1.
+    #[must_use]
+    pub fn from_seed(width: usize, height: usize, seed: u64, density: f64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed);
 
-        #[cfg(not(target_arch = "wasm32"))]
-        let iter = iter.par_bridge();
+        let cells = (0..width * height)
+            .map(|_| CellState::from(rng.gen_bool(density)))
+            .collect();
 
-        let cells = iter.map(CellState::from).collect();
+        Self::from_cells(width, height, cells)
+    }
+
+    /// Creates a board where every cell is dead.
+    #[must_use]
+    pub fn empty(width: usize, height: usize) -> Self {
+        Self::from_cells(width, height, vec![CellState::Dead; width * height])
+    }
+
+    /// Creates a board from its cells, ordered row by row.
+    ///
+    /// # Panics
+    /// When the number of cells does not match the size of the board.
+    #[must_use]
+    pub fn from_cells(width: usize, height: usize, cells: Vec<CellState>) -> Self {
+        assert_eq!(
+            cells.len(),
+            width * height,
+            "a {width}x{height} board must have {} cells",
+            width * height
+        );
 
         Self {
-            cells,
+            cells: Cells::Bytes(cells),
             width,
             height,
+            rule: Rule::default(),
+            boundary: Boundary::default(),
+            neighbourhood: Arc::new(Moore::default()),
+            active: None,
+            edited: Vec::new(),
+            generation: 0,
+            cycles: CycleDetector::default(),
+            period: None,
+            history: None,
+            viewport: Coordinates { y: 0, x: 0 },
+            species: None,
         }
     }
 
+    /// Sets the rule used to step the state, which defaults to Conway's game of life.
+    ///
+    /// Under a rule with more than one species, every cell starts as the first species, unless it already had one.
+    #[must_use]
+    pub fn with_rule(self, rule: Rule) -> Self {
+        let storage = self.storage();
+
+        let species = (rule.species() > 1).then(|| match self.species {
+            Some(species) => species
+                .into_iter()
+                .map(|species| species % rule.species())
+                .collect(),
+            None => vec![0; self.width * self.height],
+        });
+
+        Self {
+            rule,
+            active: None,
+            species,
+            ..self
+        }
+        .with_storage(storage)
+        .forget_cycles()
+    }
+
+    /// Sets how cells beyond the edges of the board are treated, which defaults to wrapping around.
+    #[must_use]
+    pub fn with_boundary(self, boundary: Boundary) -> Self {
+        Self {
+            boundary,
+            active: None,
+            ..self
+        }
+        .forget_cycles()
+    }
+
+    /// Sets which cells are the neighbours of each cell, which defaults to the eight surrounding cells.
+    #[must_use]
+    pub fn with_neighbourhood(self, neighbourhood: Arc<dyn Neighbourhood>) -> Self {
+        let storage = self.storage();
+
+        Self {
+            neighbourhood,
+            active: None,
+            ..self
+        }
+        .with_storage(storage)
+        .forget_cycles()
+    }
+
+    /// Sets how the cells are stored, which defaults to [`Storage::Bytes`].
+    ///
+    /// An unbounded board is placed on the plane at [`State::viewport`], and only the cells in the viewport are kept
+    /// when it is stored another way.
+    #[must_use]
+    pub fn with_storage(self, storage: Storage) -> Self {
+        let Self { width, height, .. } = self;
+
+        let storage = match storage {
+            Storage::Packed
+                if self.rule.states() > 2
+                    || self.rule.species() > 1
+                    || !neighbourhood::is_moore(&*self.neighbourhood) =>
+            {
+                Storage::Bytes
+            }
+            Storage::Unbounded if self.rule.is_born(0) || self.rule.species() > 1 => Storage::Bytes,
+            storage => storage,
+        };
+
+        let cells = if storage == self.storage() {
+            self.cells
+        } else {
+            let cells = self.cells();
+            match storage {
+                Storage::Bytes => Cells::Bytes(cells),
+                Storage::Packed => Cells::Packed(PackedCells::from_cells(width, height, &cells)),
+                Storage::Unbounded => {
+                    Cells::Unbounded(ChunkedCells::from_cells(self.viewport, width, &cells))
+                }
+            }
+        };
+
+        let history = if storage == Storage::Unbounded {
+            None
+        } else {
+            self.history
+        };
+
+        Self {
+            cells,
+            history,
+            ..self
+        }
+        .forget_cycles()
+    }
+
+    /// Gives each cell a random species, under a rule with more than one species, where the same seed always gives the
+    /// same species.
+    #[must_use]
+    pub fn with_random_species(self, seed: u64) -> Self {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let count = self.rule.species();
+
+        let species = self.species.map(|species| {
+            species
+                .into_iter()
+                .map(|_| rng.gen_range(0..count))
+                .collect()
+        });
+
+        Self { species, ..self }.forget_cycles()
+    }
+
+    /// Sets the generation the board is at, such as after jumping ahead.
+    ///
+    /// The history is forgotten, as the generations it remembers are numbered from the earlier generation.
+    #[must_use]
+    pub fn with_generation(mut self, generation: u64) -> Self {
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+
+        Self { generation, ..self }.forget_cycles()
+    }
+
+    /// Remembers earlier generations, so the board can be stepped backwards, using at most `capacity` bytes.
+    ///
+    /// Only the cells changed by each step are remembered, so boards which are mostly still can be rewound much
+    /// further. Once the history is full, the oldest generations are forgotten.
+    ///
+    /// Unbounded boards have no history, so this leaves them unchanged.
+    #[must_use]
+    pub fn with_history(self, capacity: usize) -> Self {
+        let history = (self.storage() != Storage::Unbounded).then(|| History::new(capacity));
+
+        Self { history, ..self }
+    }
+
+    /// Sets the position on the plane of the top left cell of the board.
+    #[must_use]
+    pub fn with_viewport(mut self, top_left: Coordinates<i64>) -> Self {
+        self.set_viewport(top_left);
+        self
+    }
+
+    /// Creates an unbounded board with its top left cell at `viewport`, where the cells at `alive` are alive, even
+    /// when they are beyond the viewport.
+    pub(crate) fn unbounded_from_alive(
+        width: usize,
+        height: usize,
+        viewport: Coordinates<i64>,
+        rule: Rule,
+        alive: impl IntoIterator<Item = Coordinates<i64>>,
+    ) -> Self {
+        let mut state = Self::empty(width, height)
+            .with_rule(rule)
+            .with_viewport(viewport)
+            .with_storage(Storage::Unbounded);
+
+        if let Cells::Unbounded(cells) = &mut state.cells {
+            for position in alive {
+                cells.set(position, CellState::Alive);
+            }
+        }
+
+        state
+    }
+
+    /// Forgets the generations seen so far, as the board no longer follows on from them.
+    fn forget_cycles(self) -> Self {
+        Self {
+            cycles: CycleDetector::default(),
+            period: None,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn storage(&self) -> Storage {
+        match self.cells {
+            Cells::Bytes(_) => Storage::Bytes,
+            Cells::Packed(_) => Storage::Packed,
+            Cells::Unbounded(_) => Storage::Unbounded,
+        }
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    #[must_use]
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    #[must_use]
+    pub fn neighbourhood(&self) -> &dyn Neighbourhood {
+        &*self.neighbourhood
+    }
+
+    /// Gets the position on the plane of the top left cell of the board.
+    #[must_use]
+    pub fn viewport(&self) -> Coordinates<i64> {
+        self.viewport
+    }
+
+    /// Moves the board to show another window of the plane, with its top left cell at `top_left`.
+    ///
+    /// Only unbounded boards have cells beyond their edges, so other boards are only moved on the plane, and show
+    /// the same cells.
+    pub fn set_viewport(&mut self, top_left: Coordinates<i64>) {
+        if let Cells::Unbounded(_) = self.cells {
+            if top_left != self.viewport {
+                self.edited.extend(0..self.width * self.height);
+            }
+        }
+
+        self.viewport = top_left;
+    }
+
+    /// Changes the size of the board, keeping its top left cell in place, so cells beyond the new edges are cropped
+    /// and new cells are dead.
+    ///
+    /// Unbounded boards keep every cell, as only their viewport changes. The history is forgotten, as the earlier
+    /// generations were a different size.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+
+        match self.storage() {
+            Storage::Bytes => self.cells = Cells::Bytes(self.window(self.viewport, width, height)),
+            Storage::Packed => {
+                let cells = self.window(self.viewport, width, height);
+                self.cells = Cells::Packed(PackedCells::from_cells(width, height, &cells));
+            }
+            Storage::Unbounded => {}
+        }
+
+        if let Some(species) = &mut self.species {
+            let old_width = self.width;
+            *species = (0..width * height)
+                .map(|index| {
+                    let (x, y) = (index % width, index / width);
+                    if x < old_width && y < self.height {
+                        species[y * old_width + x]
+                    } else {
+                        0
+                    }
+                })
+                .collect();
+        }
+
+        self.width = width;
+        self.height = height;
+
+        // Every cell may have moved to a new index, so the whole board is stepped and rendered again
+        self.active = None;
+        self.edited = (0..width * height).collect();
+        self.cycles = CycleDetector::default();
+        self.period = None;
+
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+    }
+
+    /// Copies the cells in any rectangle of the plane, ordered row by row, where cells beyond the edges of a board
+    /// which is not unbounded are dead.
+    #[must_use]
+    pub fn window(
+        &self,
+        top_left: Coordinates<i64>,
+        width: usize,
+        height: usize,
+    ) -> Vec<CellState> {
+        if let Cells::Unbounded(cells) = &self.cells {
+            return cells.window(top_left, width, height);
+        }
+
+        (0..width * height)
+            .map(|index| {
+                self.viewport_index(ChunkedCells::position(top_left, width, index))
+                    .map_or(CellState::Dead, |index| self.cell(index))
+            })
+            .collect()
+    }
+
+    /// Finds the index of the cell at a position on the plane, or `None` when it is outside the viewport.
+    fn viewport_index(&self, position: Coordinates<i64>) -> Option<usize> {
+        let y = usize::try_from(position.y - self.viewport.y).ok()?;
+        let x = usize::try_from(position.x - self.viewport.x).ok()?;
+
+        (y < self.height && x < self.width).then_some(y * self.width + x)
+    }
+
+    /// Gets the number of generations the board has been stepped.
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Counts the alive cells.
+    #[must_use]
+    pub fn population(&self) -> usize {
+        match &self.cells {
+            Cells::Bytes(cells) => cells
+                .iter()
+                .filter(|&&state| state == CellState::Alive)
+                .count(),
+            Cells::Packed(cells) => cells.population(),
+            Cells::Unbounded(cells) => cells.population(),
+        }
+    }
+
+    /// Finds the position on the plane of every alive cell, including those beyond the viewport of an unbounded
+    /// board.
+    pub(crate) fn alive_positions(&self) -> Vec<Coordinates<i64>> {
+        if let Cells::Unbounded(cells) = &self.cells {
+            return cells.alive().collect();
+        }
+
+        (0..self.width * self.height)
+            .filter(|&index| self.cell(index) == CellState::Alive)
+            .map(|index| ChunkedCells::position(self.viewport, self.width, index))
+            .collect()
+    }
+
+    /// Finds the smallest rectangle holding every alive cell, or `None` when every cell is dead.
+    ///
+    /// The rectangle of an unbounded board holds alive cells beyond the viewport too, so its top left cell can be
+    /// above or to the left of the board.
+    #[must_use]
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        if let Cells::Unbounded(cells) = &self.cells {
+            let (top_left, bottom_right) = cells.bounds()?;
+
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            return Some(BoundingBox {
+                top_left: Coordinates {
+                    y: (top_left.y - self.viewport.y) as i32,
+                    x: (top_left.x - self.viewport.x) as i32,
+                },
+                width: (bottom_right.x - top_left.x) as usize + 1,
+                height: (bottom_right.y - top_left.y) as usize + 1,
+            });
+        }
+
+        let (mut top_left, mut bottom_right) = (None::<Coordinates>, None::<Coordinates>);
+
+        for index in
+            (0..self.width * self.height).filter(|&index| self.cell(index) == CellState::Alive)
+        {
+            let Coordinates { y, x } = self.get_coordinates(index);
+
+            top_left = Some(
+                top_left.map_or(Coordinates { y, x }, |top_left| Coordinates {
+                    y: top_left.y.min(y),
+                    x: top_left.x.min(x),
+                }),
+            );
+            bottom_right =
+                Some(
+                    bottom_right.map_or(Coordinates { y, x }, |bottom_right| Coordinates {
+                        y: bottom_right.y.max(y),
+                        x: bottom_right.x.max(x),
+                    }),
+                );
+        }
+
+        let (top_left, bottom_right) = (top_left?, bottom_right?);
+
+        #[allow(clippy::cast_sign_loss)]
+        Some(BoundingBox {
+            top_left,
+            width: (bottom_right.x - top_left.x) as usize + 1,
+            height: (bottom_right.y - top_left.y) as usize + 1,
+        })
+    }
+
+    /// Finds whether the board has died out or settled into a cycle.
+    ///
+    /// Cycles are found by comparing the board with the boards of recent generations, so a cycle is reported once
+    /// the board has been stepped through it once.
+    #[must_use]
+    pub fn status(&self) -> Status {
+        match self.period {
+            _ if self.is_empty() => Status::Extinct,
+            Some(1) => Status::StillLife,
+            Some(period) => Status::Oscillator { period },
+            None => Status::Running,
+        }
+    }
+
+    /// Finds whether every cell is dead, where dying cells still count as they will keep changing.
+    fn is_empty(&self) -> bool {
+        match &self.cells {
+            Cells::Bytes(cells) => cells.iter().all(|&state| state == CellState::Dead),
+            // Packed cells are only ever alive or dead
+            Cells::Packed(cells) => cells.population() == 0,
+            Cells::Unbounded(cells) => cells.occupied().next().is_none(),
+        }
+    }
+
+    fn cells_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        match &self.cells {
+            Cells::Bytes(cells) => cells.hash(&mut hasher),
+            Cells::Packed(cells) => cells.hash(&mut hasher),
+            Cells::Unbounded(cells) => cells.hash(&mut hasher),
+        }
+        // The colours of a board can still be changing once its cells repeat
+        self.species.hash(&mut hasher);
+        hasher.finish()
+    }
+
     fn get_coordinates(&self, index: usize) -> Coordinates {
         let Self { width, .. } = self;
 
@@ -78,11 +674,17 @@ impl State {
         }
     }
 
-    fn get_neighbours(&self, coordinates: Coordinates) -> [CellState; 8] {
+    /// Finds the indices of the cells at some offsets from a cell, which are `None` when they are dead cells beyond
+    /// the edge.
+    fn cells_at_offsets<'a>(
+        &'a self,
+        coordinates: Coordinates,
+        offsets: impl IntoIterator<Item = Coordinates> + 'a,
+    ) -> impl Iterator<Item = Option<usize>> + 'a {
         let Self {
-            cells,
             width,
             height,
+            boundary,
             ..
         } = self;
 
@@ -91,87 +693,445 @@ impl State {
         #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
         let (width, height) = (*width as _, *height as _);
 
-        [
-            (-1, -1),
-            (-1, 0),
-            (-1, 1),
-            (0, -1),
-            (0, 1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-        ]
-        .map(|(x_shift, y_shift)| {
-            let x = (x + x_shift).rem_euclid(width);
-            let y = (y + y_shift).rem_euclid(height);
-            y * width + x
+        offsets.into_iter().map(move |offset| {
+            boundary
+                .resolve(
+                    Coordinates {
+                        x: x + offset.x,
+                        y: y + offset.y,
+                    },
+                    width,
+                    height,
+                )
+                .map(
+                    #[allow(clippy::cast_sign_loss)]
+                    {
+                        |coordinates| coordinates.to_index(width) as usize
+                    },
+                )
         })
-        .map(
-            #[allow(clippy::cast_sign_loss)]
-            {
-                |index| cells[index as usize]
-            },
-        )
     }
 
     fn get_alive_neighbours_count(&self, coordinates: Coordinates) -> usize {
-        self.get_neighbours(coordinates)
+        let offsets = self.neighbourhood.offsets(coordinates).iter().copied();
+
+        self.cells_at_offsets(coordinates, offsets)
+            .flatten()
+            .filter(|&index| self.cell(index) == CellState::Alive)
+            .count()
+    }
+
+    /// Finds the species of a cell born at an index, from the species of its alive neighbours.
+    fn newborn_species(&self, index: usize, species: &[u8]) -> u8 {
+        let coordinates = self.get_coordinates(index);
+        let offsets = self.neighbourhood.offsets(coordinates).iter().copied();
+
+        let parents = self
+            .cells_at_offsets(coordinates, offsets)
+            .flatten()
+            .filter(|&index| self.cell(index) == CellState::Alive)
+            .map(|index| species[index]);
+
+        self.rule.newborn_species(parents)
+    }
+
+    fn cell(&self, index: usize) -> CellState {
+        match &self.cells {
+            Cells::Bytes(cells) => cells[index],
+            Cells::Packed(cells) => cells.get(index),
+            Cells::Unbounded(cells) => {
+                cells.get(ChunkedCells::position(self.viewport, self.width, index))
+            }
+        }
+    }
+
+    /// Finds the cells that can change in the next step, which are the active cells and their neighbours.
+    fn candidates(&self, active: &[usize]) -> Vec<usize> {
+        let mut candidates = active
             .iter()
-            .fold(0, |acc, &state| acc + state as usize)
+            .flat_map(|&index| {
+                // The cells which have this cell as a neighbour are found by going back along each offset
+                let offsets = self.neighbourhood.reach().iter().map(|offset| Coordinates {
+                    y: -offset.y,
+                    x: -offset.x,
+                });
+                let affected = self.cells_at_offsets(self.get_coordinates(index), offsets);
+
+                std::iter::once(index).chain(affected.flatten())
+            })
+            .collect::<Vec<_>>();
+
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
     }
 
-    fn next_state_buffer(&mut self) -> Vec<CellRenderInfo> {
+    /// Finds the cells which change in the next step along with their new states.
+    fn byte_updates(&self, cells: &[CellState]) -> Vec<(usize, CellState)> {
+        let candidates = match &self.active {
+            Some(active) => self.candidates(active),
+            None => (0..cells.len()).collect(),
+        };
+
         #[cfg(not(target_arch = "wasm32"))]
-        let cells = self.cells.par_iter();
+        let candidates = candidates.into_par_iter();
 
         #[cfg(target_arch = "wasm32")]
-        let cells = self.cells.iter();
-
-        let (internal_state, state): (Vec<_>, Vec<_>) = cells
-            .enumerate()
-            .map(|(index, &state)| {
-                let coordinates = self.get_coordinates(index);
-
-                let next_state = match (state, self.get_alive_neighbours_count(coordinates)) {
-                    // Cells with 3 neighbours become populated or stay alive
-                    // Cells with 2 neighbours also stay alive
-                    (_, 3) | (CellState::Alive, 2) => CellState::Alive,
-                    // Cells without 3 neighbours stay dead
-                    // Cells with 0, 1 or 4+ neighbours die
-                    _ => CellState::Dead,
-                };
-
-                (
-                    next_state,
-                    CellRenderInfo {
-                        state: next_state,
-                        coordinates,
-                        needs_rerender: state != next_state,
-                    },
-                )
+        let candidates = candidates.into_iter();
+
+        candidates
+            .filter_map(|index| {
+                let state = self.rule.next_state(
+                    cells[index],
+                    self.get_alive_neighbours_count(self.get_coordinates(index)),
+                );
+                (state != cells[index]).then_some((index, state))
             })
-            .collect::<Vec<_>>()
-            .into_iter()
-            .unzip();
+            .collect()
+    }
 
-        self.cells = internal_state;
+    /// Steps the board to the next generation, returning the indices of the cells that changed.
+    ///
+    /// Only the neighbourhoods of the cells that changed in the last step are stepped when the board is stored as
+    /// bytes, so boards that are mostly still are much faster to step.
+    fn step_cells(&mut self) -> Vec<usize> {
+        if self.cycles.is_empty() {
+            self.cycles.record(self.cells_hash(), self.generation);
+        }
 
-        state
+        // The changed cells along with their earlier states and species
+        let earlier_states: Vec<Change> = match &self.cells {
+            Cells::Bytes(cells) => {
+                let updates = self.byte_updates(cells);
+                let mut earlier_states = Vec::with_capacity(updates.len());
+                let earlier_species = updates
+                    .iter()
+                    .map(|&(index, _)| self.species_at(index))
+                    .collect::<Vec<_>>();
+
+                // The species of newborn cells are found before any cell changes, as they depend on the parents
+                let births = self.species.as_deref().map(|species| {
+                    updates
+                        .iter()
+                        .filter(|&&(_, state)| state == CellState::Alive)
+                        .map(|&(index, _)| (index, self.newborn_species(index, species)))
+                        .collect::<Vec<_>>()
+                });
+                if let (Some(species), Some(births)) = (&mut self.species, births) {
+                    for (index, born) in births {
+                        species[index] = born;
+                    }
+                }
+
+                if let Cells::Bytes(cells) = &mut self.cells {
+                    for ((index, state), species) in updates.into_iter().zip(earlier_species) {
+                        let earlier = std::mem::replace(&mut cells[index], state);
+                        earlier_states.push((index, earlier, species));
+                    }
+                }
+
+                earlier_states
+            }
+            Cells::Packed(cells) => {
+                let next = cells.step(self.rule, self.boundary);
+                let earlier_states = cells
+                    .changed_indices(&next)
+                    .into_iter()
+                    .map(|index| (index, cells.get(index), 0))
+                    .collect();
+                self.cells = Cells::Packed(next);
+                earlier_states
+            }
+            Cells::Unbounded(cells) => {
+                let (next, changes) = cells.step(self.rule, &*self.neighbourhood);
+                self.cells = Cells::Unbounded(next);
+                changes
+                    .into_iter()
+                    .filter_map(|(position, state)| {
+                        Some((self.viewport_index(position)?, state, 0))
+                    })
+                    .collect()
+            }
+        };
+
+        let changed = earlier_states
+            .iter()
+            .map(|&(index, ..)| index)
+            .collect::<Vec<_>>();
+        if let Some(history) = &mut self.history {
+            history.record_step(earlier_states);
+        }
+
+        self.active = Some(changed.clone());
+        self.edited.clear();
+
+        self.generation += 1;
+        let period = self.cycles.record(self.cells_hash(), self.generation);
+        self.period = self.period.or(period);
+
+        changed
+    }
+
+    /// Steps the board to the next generation, without building a frame.
+    pub fn step(&mut self) {
+        self.step_cells();
+    }
+
+    /// Gets the earliest generation the board can be stepped back to.
+    #[must_use]
+    pub fn earliest_generation(&self) -> u64 {
+        self.generation
+            - self
+                .history
+                .as_ref()
+                .map_or(0, |history| history.len() as u64)
+    }
+
+    /// Steps the board back to the previous generation, as it was when it was first reached, which also undoes any
+    /// edits made since then.
+    ///
+    /// Returns whether the board could be stepped back, which needs a history from [`State::with_history`].
+    pub fn step_back(&mut self) -> bool {
+        let Some(changes) = self.history.as_mut().and_then(History::undo) else {
+            return false;
+        };
+
+        for (index, state, species) in changes {
+            if let Some(cell_species) = self
+                .species
+                .as_mut()
+                .and_then(|cell_species| cell_species.get_mut(index))
+            {
+                *cell_species = species;
+            }
+
+            match &mut self.cells {
+                Cells::Bytes(cells) => cells[index] = state,
+                Cells::Packed(cells) => {
+                    cells.set(index, state);
+                }
+                Cells::Unbounded(cells) => {
+                    cells.set(
+                        ChunkedCells::position(self.viewport, self.width, index),
+                        state,
+                    );
+                }
+            }
+            self.edited.push(index);
+        }
+
+        self.generation -= 1;
+        self.active = None;
+        self.cycles = CycleDetector::default();
+        self.period = None;
+
+        true
+    }
+
+    /// Steps the board backwards or forwards until it reaches a generation.
+    ///
+    /// Returns whether the generation could be reached, where the board is left unchanged when it is earlier than
+    /// [`State::earliest_generation`].
+    pub fn jump_to(&mut self, generation: u64) -> bool {
+        if generation < self.earliest_generation() {
+            return false;
+        }
+
+        while self.generation > generation {
+            self.step_back();
+        }
+        while self.generation < generation {
+            self.step_cells();
+        }
+
+        true
+    }
+
+    /// Steps the board to the next generation, and creates a frame of the changes.
+    ///
+    /// The frame holds the cells changed by the step along with any cells edited since the last step, so a diff
+    /// frame only describes the whole board when every previous generation was also rendered.
+    pub fn next_frame(&mut self, kind: FrameKind) -> Frame {
+        let mut changed = std::mem::take(&mut self.edited);
+        changed.extend(self.step_cells());
+        changed.sort_unstable();
+        changed.dedup();
+
+        let buffer = match kind {
+            FrameKind::Full => {
+                let mut needs_rerender = vec![false; self.width * self.height];
+                for index in changed {
+                    needs_rerender[index] = true;
+                }
+
+                self.cells()
+                    .into_iter()
+                    .zip(needs_rerender)
+                    .enumerate()
+                    .map(|(index, (state, needs_rerender))| CellRenderInfo {
+                        state,
+                        species: self.species_at(index),
+                        coordinates: self.get_coordinates(index),
+                        needs_rerender,
+                    })
+                    .collect()
+            }
+            FrameKind::Diff => changed
+                .into_iter()
+                .map(|index| CellRenderInfo {
+                    state: self.cell(index),
+                    species: self.species_at(index),
+                    coordinates: self.get_coordinates(index),
+                    needs_rerender: true,
+                })
+                .collect(),
+        };
+
+        Frame { buffer, kind }
+    }
+
+    /// Creates a frame of the current cells, where every cell needs to be rendered.
+    #[must_use]
+    pub fn frame(&self) -> Frame {
+        Frame {
+            buffer: self
+                .cells()
+                .into_iter()
+                .enumerate()
+                .map(|(index, state)| CellRenderInfo {
+                    state,
+                    species: self.species_at(index),
+                    coordinates: self.get_coordinates(index),
+                    needs_rerender: true,
+                })
+                .collect(),
+            kind: FrameKind::Full,
+        }
+    }
+
+    /// Creates a diff frame of some cells, such as those which have just been edited.
+    pub(crate) fn frame_of(&self, indices: impl IntoIterator<Item = usize>) -> Frame {
+        Frame {
+            buffer: indices
+                .into_iter()
+                .map(|index| CellRenderInfo {
+                    state: self.cell(index),
+                    species: self.species_at(index),
+                    coordinates: self.get_coordinates(index),
+                    needs_rerender: true,
+                })
+                .collect(),
+            kind: FrameKind::Diff,
+        }
     }
 
     pub fn replace_at_index(&mut self, index: usize, new_state: CellState) -> Option<CellState> {
-        let state = self.cells.get_mut(index)?;
-        Some(std::mem::replace(state, new_state))
+        let previous = match &mut self.cells {
+            Cells::Bytes(cells) => {
+                let state = cells.get_mut(index)?;
+                std::mem::replace(state, new_state)
+            }
+            Cells::Packed(cells) => {
+                (index < self.width * self.height).then(|| cells.set(index, new_state))?
+            }
+            Cells::Unbounded(cells) => (index < self.width * self.height).then(|| {
+                cells.set(
+                    ChunkedCells::position(self.viewport, self.width, index),
+                    new_state,
+                )
+            })?,
+        };
+
+        if previous != new_state {
+            self.edited.push(index);
+            if let Some(active) = &mut self.active {
+                active.push(index);
+            }
+
+            self.cycles = CycleDetector::default();
+            self.period = None;
+
+            let species = self.species_at(index);
+            if let Some(history) = &mut self.history {
+                history.record_edit(index, previous, species);
+            }
+        }
+
+        Some(previous)
+    }
+
+    /// Kills every cell on the board, or in the viewport of an unbounded board.
+    ///
+    /// Each cell is edited as if by [`State::replace_at_index`], so clearing can be stepped back.
+    pub fn clear(&mut self) {
+        for index in 0..self.width * self.height {
+            self.replace_at_index(index, CellState::Dead);
+        }
+    }
+
+    /// Refills the board, or the viewport of an unbounded board, in the same way as [`State::from_seed`].
+    ///
+    /// Each cell is edited as if by [`State::replace_at_index`], so randomising can be stepped back.
+    ///
+    /// # Panics
+    /// When `density` is not between 0 and 1.
+    pub fn randomise(&mut self, seed: u64, density: f64) {
+        let mut rng = SmallRng::seed_from_u64(seed);
+
+        for index in 0..self.width * self.height {
+            self.replace_at_index(index, CellState::from(rng.gen_bool(density)));
+        }
+    }
+
+    /// Gets the species of a cell, which is always 0 under a rule with only one species.
+    #[must_use]
+    pub fn species_at(&self, index: usize) -> u8 {
+        self.species
+            .as_ref()
+            .and_then(|species| species.get(index).copied())
+            .unwrap_or(0)
+    }
+
+    /// Changes the species of a cell, under a rule with more than one species, so it is rendered again.
+    ///
+    /// The change is remembered by the history, so it can be stepped back like any other edit.
+    pub fn set_species(&mut self, index: usize, new_species: u8) {
+        let count = self.rule.species();
+        let Some(species) = self
+            .species
+            .as_mut()
+            .and_then(|species| species.get_mut(index))
+        else {
+            return;
+        };
+
+        let new_species = new_species % count;
+        if *species != new_species {
+            let previous = std::mem::replace(species, new_species);
+            self.edited.push(index);
+
+            self.cycles = CycleDetector::default();
+            self.period = None;
+
+            let state = self.cell(index);
+            if let Some(history) = &mut self.history {
+                history.record_edit(index, state, previous);
+            }
+        }
     }
 
     #[must_use]
     pub fn at_index(&self, index: usize) -> Option<CellState> {
-        self.cells.get(index).copied()
+        (index < self.width * self.height).then(|| self.cell(index))
     }
 
     #[must_use]
     pub fn cells(&self) -> Vec<CellState> {
-        self.cells.clone()
+        match &self.cells {
+            Cells::Bytes(cells) => cells.clone(),
+            Cells::Packed(cells) => cells.to_cells(),
+            Cells::Unbounded(cells) => cells.window(self.viewport, self.width, self.height),
+        }
     }
 }
 
@@ -179,9 +1139,7 @@ impl std::iter::Iterator for State {
     type Item = Frame;
 
     fn next(&mut self) -> Option<Self::Item> {
-        Some(Frame {
-            buffer: self.next_state_buffer(),
-        })
+        Some(self.next_frame(FrameKind::Full))
     }
 }
 
@@ -195,6 +1153,12 @@ impl IntoIterator for Frame {
 }
 
 impl Frame {
+    #[must_use]
+    pub fn kind(&self) -> FrameKind {
+        self.kind
+    }
+
+    /// Maps the state of every cell into a buffer, ordered row by row, which only makes sense for full frames.
     pub fn to_buffer<F, R>(self, state_mapping: F) -> Vec<R>
     where
         F: Fn(CellState) -> R,
@@ -0,0 +1,367 @@
+use super::CellState;
+use crate::{neighbourhood::Neighbourhood, rule::Rule, Coordinates};
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+
+/// The width and height of a chunk, which must be even so every chunk agrees on which cells have even coordinates.
+const CHUNK_SIZE: i32 = 64;
+const CHUNK_AREA: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// The cells of a chunk, row by row.
+type Chunk = Box<[CellState]>;
+
+/// Cells on an unbounded plane, stored as square chunks where only chunks with cells that are not dead are kept.
+///
+/// The plane grows as activity spreads into new chunks, and shrinks as chunks die out.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub(super) struct ChunkedCells {
+    chunks: HashMap<Coordinates<i64>, Chunk>,
+}
+
+/// Splits a position on the plane into the position of its chunk and its index in the chunk.
+fn split(Coordinates { y, x }: Coordinates<i64>) -> (Coordinates<i64>, usize) {
+    let size = i64::from(CHUNK_SIZE);
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let index = (y.rem_euclid(size) * size + x.rem_euclid(size)) as usize;
+
+    (
+        Coordinates {
+            y: y.div_euclid(size),
+            x: x.div_euclid(size),
+        },
+        index,
+    )
+}
+
+/// Finds the position on the plane of a cell in a chunk.
+fn join(chunk: Coordinates<i64>, index: usize) -> Coordinates<i64> {
+    let size = i64::from(CHUNK_SIZE);
+
+    #[allow(clippy::cast_possible_wrap)]
+    let index = index as i64;
+
+    Coordinates {
+        y: chunk.y * size + index / size,
+        x: chunk.x * size + index % size,
+    }
+}
+
+impl ChunkedCells {
+    /// Places the cells of a board on the plane, with its top left cell at `top_left`.
+    pub fn from_cells(top_left: Coordinates<i64>, width: usize, cells: &[CellState]) -> Self {
+        let mut chunked = Self::default();
+
+        for (index, &state) in cells.iter().enumerate() {
+            chunked.set(Self::position(top_left, width, index), state);
+        }
+
+        chunked
+    }
+
+    /// Finds the position on the plane of a cell in a window of the plane.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn position(top_left: Coordinates<i64>, width: usize, index: usize) -> Coordinates<i64> {
+        Coordinates {
+            y: top_left.y + (index / width) as i64,
+            x: top_left.x + (index % width) as i64,
+        }
+    }
+
+    /// Copies the cells in a window of the plane, row by row.
+    pub fn window(
+        &self,
+        top_left: Coordinates<i64>,
+        width: usize,
+        height: usize,
+    ) -> Vec<CellState> {
+        (0..width * height)
+            .map(|index| self.get(Self::position(top_left, width, index)))
+            .collect()
+    }
+
+    pub fn get(&self, position: Coordinates<i64>) -> CellState {
+        let (chunk, index) = split(position);
+        self.chunks
+            .get(&chunk)
+            .map_or(CellState::Dead, |chunk| chunk[index])
+    }
+
+    pub fn set(&mut self, position: Coordinates<i64>, state: CellState) -> CellState {
+        let (chunk, index) = split(position);
+
+        if state == CellState::Dead && !self.chunks.contains_key(&chunk) {
+            return CellState::Dead;
+        }
+
+        let chunk = self
+            .chunks
+            .entry(chunk)
+            .or_insert_with(|| vec![CellState::Dead; CHUNK_AREA].into_boxed_slice());
+
+        std::mem::replace(&mut chunk[index], state)
+    }
+
+    pub fn population(&self) -> usize {
+        self.chunks
+            .values()
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .filter(|&&state| state == CellState::Alive)
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Finds the position and state of every cell which is not dead, in no particular order.
+    pub fn occupied(&self) -> impl Iterator<Item = (Coordinates<i64>, CellState)> + '_ {
+        self.chunks.iter().flat_map(|(&position, chunk)| {
+            chunk
+                .iter()
+                .enumerate()
+                .filter(|&(_, &state)| state != CellState::Dead)
+                .map(move |(index, &state)| (join(position, index), state))
+        })
+    }
+
+    /// Finds the position of every alive cell, in no particular order.
+    pub fn alive(&self) -> impl Iterator<Item = Coordinates<i64>> + '_ {
+        self.occupied()
+            .filter(|&(_, state)| state == CellState::Alive)
+            .map(|(position, _)| position)
+    }
+
+    /// Finds the top left and bottom right alive cells of the smallest rectangle holding every alive cell.
+    pub fn bounds(&self) -> Option<(Coordinates<i64>, Coordinates<i64>)> {
+        self.alive().fold(None, |bounds, position| {
+            let (top_left, bottom_right) = bounds.unwrap_or((position, position));
+            Some((
+                Coordinates {
+                    y: top_left.y.min(position.y),
+                    x: top_left.x.min(position.x),
+                },
+                Coordinates {
+                    y: bottom_right.y.max(position.y),
+                    x: bottom_right.x.max(position.x),
+                },
+            ))
+        })
+    }
+
+    /// Steps every cell which could change to the next generation, returning the new cells along with the position
+    /// and earlier state of each cell that changed.
+    ///
+    /// Neighbours are only looked for in the surrounding chunks, so the neighbourhood must fit within a chunk.
+    pub fn step(
+        &self,
+        rule: Rule,
+        neighbourhood: &dyn Neighbourhood,
+    ) -> (Self, Vec<(Coordinates<i64>, CellState)>) {
+        let radius = neighbourhood
+            .reach()
+            .iter()
+            .map(|offset| offset.y.abs().max(offset.x.abs()))
+            .max()
+            .unwrap_or(0);
+
+        // Only cells near a cell which is not dead can change, so the rest of each chunk is left as it is
+        let mut regions = HashMap::<Coordinates<i64>, Region>::new();
+        for (&position, chunk) in &self.chunks {
+            let Some(region) = Region::of(chunk) else {
+                continue;
+            };
+
+            for y_shift in -1..=1 {
+                for x_shift in -1..=1 {
+                    let Some(region) = region.grown(radius).shifted(y_shift, x_shift) else {
+                        continue;
+                    };
+
+                    regions
+                        .entry(Coordinates {
+                            y: position.y + i64::from(y_shift),
+                            x: position.x + i64::from(x_shift),
+                        })
+                        .and_modify(|existing| *existing = existing.union(region))
+                        .or_insert(region);
+                }
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let regions = regions.into_par_iter();
+
+        #[cfg(target_arch = "wasm32")]
+        let regions = regions.into_iter();
+
+        let stepped = regions
+            .map(|(position, region)| {
+                (
+                    position,
+                    self.step_chunk(position, region, rule, neighbourhood),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut chunks = HashMap::new();
+        let mut changes = Vec::new();
+        for (position, (chunk, chunk_changes)) in stepped {
+            changes.extend(chunk_changes);
+            if let Some(chunk) = chunk {
+                chunks.insert(position, chunk);
+            }
+        }
+
+        (Self { chunks }, changes)
+    }
+
+    /// Steps the cells in a region of one chunk, returning the chunk, which is `None` when every cell is dead.
+    fn step_chunk(
+        &self,
+        position: Coordinates<i64>,
+        region: Region,
+        rule: Rule,
+        neighbourhood: &dyn Neighbourhood,
+    ) -> (Option<Chunk>, Vec<(Coordinates<i64>, CellState)>) {
+        let surrounding = [-1, 0, 1].map(|y_shift| {
+            [-1, 0, 1].map(|x_shift| {
+                self.chunks.get(&Coordinates {
+                    y: position.y + y_shift,
+                    x: position.x + x_shift,
+                })
+            })
+        });
+
+        // Gets a cell by its position relative to the top left of this chunk
+        let cell = |Coordinates { y, x }: Coordinates| {
+            #[allow(clippy::cast_sign_loss)]
+            let [chunk_y, chunk_x, y, x] = [
+                y.div_euclid(CHUNK_SIZE) + 1,
+                x.div_euclid(CHUNK_SIZE) + 1,
+                y.rem_euclid(CHUNK_SIZE),
+                x.rem_euclid(CHUNK_SIZE),
+            ]
+            .map(|value| value as usize);
+
+            #[allow(clippy::cast_sign_loss)]
+            surrounding[chunk_y][chunk_x]
+                .map_or(CellState::Dead, |chunk| chunk[y * CHUNK_SIZE as usize + x])
+        };
+
+        let mut changes = Vec::new();
+        let mut next = surrounding[1][1].map_or_else(
+            || vec![CellState::Dead; CHUNK_AREA].into_boxed_slice(),
+            Clone::clone,
+        );
+
+        for y in region.top..=region.bottom {
+            for x in region.left..=region.right {
+                let coordinates = Coordinates { y, x };
+                let alive_neighbours = neighbourhood
+                    .offsets(coordinates)
+                    .iter()
+                    .filter(|offset| {
+                        cell(Coordinates {
+                            y: y + offset.y,
+                            x: x + offset.x,
+                        }) == CellState::Alive
+                    })
+                    .count();
+
+                let state = cell(coordinates);
+                let next_state = rule.next_state(state, alive_neighbours);
+                if next_state != state {
+                    #[allow(clippy::cast_sign_loss)]
+                    let index = (y * CHUNK_SIZE + x) as usize;
+
+                    changes.push((join(position, index), state));
+                    next[index] = next_state;
+                }
+            }
+        }
+
+        let chunk = next
+            .iter()
+            .any(|&state| state != CellState::Dead)
+            .then_some(next);
+
+        (chunk, changes)
+    }
+}
+
+/// A rectangle of cells in a chunk, as its first and last rows and columns.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Region {
+    top: i32,
+    bottom: i32,
+    left: i32,
+    right: i32,
+}
+
+impl Region {
+    /// Finds the smallest region holding every cell of a chunk which is not dead.
+    fn of(chunk: &[CellState]) -> Option<Self> {
+        chunk
+            .iter()
+            .enumerate()
+            .filter(|&(_, &state)| state != CellState::Dead)
+            .map(|(index, _)| {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let index = index as i32;
+                (index / CHUNK_SIZE, index % CHUNK_SIZE)
+            })
+            .fold(None, |region: Option<Self>, (y, x)| {
+                let cell = Self {
+                    top: y,
+                    bottom: y,
+                    left: x,
+                    right: x,
+                };
+                Some(region.map_or(cell, |region| region.union(cell)))
+            })
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self {
+            top: self.top.min(other.top),
+            bottom: self.bottom.max(other.bottom),
+            left: self.left.min(other.left),
+            right: self.right.max(other.right),
+        }
+    }
+
+    fn grown(self, radius: i32) -> Self {
+        Self {
+            top: self.top - radius,
+            bottom: self.bottom + radius,
+            left: self.left - radius,
+            right: self.right + radius,
+        }
+    }
+
+    /// Moves the region into the coordinates of a neighbouring chunk, keeping only the part inside it.
+    fn shifted(self, y_shift: i32, x_shift: i32) -> Option<Self> {
+        let region = Self {
+            top: (self.top - y_shift * CHUNK_SIZE).max(0),
+            bottom: (self.bottom - y_shift * CHUNK_SIZE).min(CHUNK_SIZE - 1),
+            left: (self.left - x_shift * CHUNK_SIZE).max(0),
+            right: (self.right - x_shift * CHUNK_SIZE).min(CHUNK_SIZE - 1),
+        };
+
+        (region.top <= region.bottom && region.left <= region.right).then_some(region)
+    }
+}
+
+impl Hash for ChunkedCells {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut chunks = self.chunks.iter().collect::<Vec<_>>();
+        chunks.sort_unstable_by_key(|(&Coordinates { y, x }, _)| (y, x));
+        chunks.hash(state);
+    }
+}
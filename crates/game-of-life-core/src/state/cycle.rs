@@ -0,0 +1,38 @@
+use std::collections::{HashMap, VecDeque};
+
+/// The most generations remembered, which is the longest period that can be detected.
+const MAX_PERIOD: usize = 4096;
+
+/// Finds cycles by remembering the hash of the board at each recent generation.
+///
+/// Only hashes are compared, so a collision could report a cycle which is not there, although with 64 bit hashes
+/// this is vanishingly unlikely.
+#[derive(Clone, Debug, Default)]
+pub(super) struct CycleDetector {
+    /// The latest generation with each hash
+    generations: HashMap<u64, u64>,
+    /// The hashes of each remembered generation, from oldest to newest
+    hashes: VecDeque<(u64, u64)>,
+}
+
+impl CycleDetector {
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Remembers the hash of a generation, returning the period of the cycle when the board has been seen before.
+    pub fn record(&mut self, hash: u64, generation: u64) -> Option<u64> {
+        if self.hashes.len() == MAX_PERIOD {
+            if let Some((oldest_hash, oldest_generation)) = self.hashes.pop_front() {
+                if self.generations.get(&oldest_hash) == Some(&oldest_generation) {
+                    self.generations.remove(&oldest_hash);
+                }
+            }
+        }
+
+        self.hashes.push_back((hash, generation));
+        self.generations
+            .insert(hash, generation)
+            .map(|previous| generation - previous)
+    }
+}
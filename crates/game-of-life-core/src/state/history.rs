@@ -0,0 +1,99 @@
+use super::CellState;
+use std::{collections::VecDeque, mem::size_of};
+
+/// A cell which changed, as its index along with its earlier state and species.
+pub(super) type Change = (usize, CellState, u8);
+
+/// The cells changed between two generations, in the order they changed.
+type Delta = Vec<Change>;
+
+/// Earlier generations of a board, remembered as the changes from each generation to the next, so a board which is
+/// mostly still takes little memory.
+#[derive(Clone, Debug)]
+pub(super) struct History {
+    /// The changes made by each step, from oldest to newest
+    deltas: VecDeque<Delta>,
+    /// The changes made by edits since the last step, which are undone along with it
+    pending: Delta,
+    /// The most memory the deltas and pending changes can take, in bytes
+    capacity: usize,
+    size: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            deltas: VecDeque::new(),
+            pending: Vec::new(),
+            capacity,
+            size: 0,
+        }
+    }
+
+    /// Gets the number of generations which can be undone.
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    /// Remembers an edit made since the last step, forgetting the oldest generations when over capacity.
+    pub fn record_edit(&mut self, index: usize, previous: CellState, previous_species: u8) {
+        self.pending.push((index, previous, previous_species));
+        self.size += size_of::<Change>();
+        self.shrink();
+    }
+
+    /// Remembers the changes made by a step, forgetting the oldest generations when over capacity.
+    pub fn record_step(&mut self, changes: impl IntoIterator<Item = Change>) {
+        let mut delta = std::mem::take(&mut self.pending);
+        let edits = delta.len();
+        delta.extend(changes);
+
+        // The pending edits are already counted
+        self.size += (delta.len() - edits) * size_of::<Change>();
+        self.deltas.push_back(delta);
+        self.shrink();
+    }
+
+    /// Forgets the oldest generations until the history fits its capacity.
+    ///
+    /// Pending edits are undone along with the newest generation, so they are only forgotten once every generation
+    /// has been, leaving the board as it was at that point as the earliest one.
+    fn shrink(&mut self) {
+        while self.size > self.capacity {
+            if let Some(oldest) = self.deltas.pop_front() {
+                self.size -= delta_size(&oldest);
+            } else {
+                self.size -= delta_size(&self.pending);
+                self.pending.clear();
+                break;
+            }
+        }
+    }
+
+    /// Forgets every earlier generation, such as when they no longer fit the board.
+    pub fn clear(&mut self) {
+        self.deltas.clear();
+        self.pending.clear();
+        self.size = 0;
+    }
+
+    /// Takes the changes made since the previous generation, in the order they must be undone.
+    pub fn undo(&mut self) -> Option<Delta> {
+        let delta = self.deltas.pop_back()?;
+        self.size -= delta_size(&delta);
+
+        let pending = std::mem::take(&mut self.pending);
+        self.size -= delta_size(&pending);
+        Some(
+            pending
+                .into_iter()
+                .rev()
+                .chain(delta.into_iter().rev())
+                .collect(),
+        )
+    }
+}
+
+fn delta_size(delta: &Delta) -> usize {
+    delta.len() * size_of::<Change>()
+}
@@ -0,0 +1,557 @@
+use super::{CellState, Cells, ChunkedCells, State, Storage};
+use crate::{
+    boundary::Boundary,
+    neighbourhood::{parse_neighbourhood, Neighbourhood, ParseNeighbourhoodError},
+    rule::{ParseRuleError, Rule},
+    Coordinates,
+};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// The bytes every snapshot starts with, where the last byte is the version of the format.
+const MAGIC: &[u8; 4] = b"GOL\x03";
+
+/// The most cells a snapshot can hold, so a corrupted snapshot can't make restoring it use too much memory.
+const MAX_CELLS: usize = 1 << 24;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub enum SnapshotError {
+    #[error("not a snapshot, or a snapshot from another version")]
+    Magic,
+    #[error("the snapshot ends early")]
+    Truncated,
+    #[error("the snapshot has unexpected bytes after its cells")]
+    TrailingBytes,
+    #[error("the snapshot has an invalid {0}")]
+    Invalid(&'static str),
+    #[error("the snapshot has an invalid rule")]
+    Rule(#[from] ParseRuleError),
+    #[error("the snapshot has an invalid neighbourhood")]
+    Neighbourhood(#[from] ParseNeighbourhoodError),
+    #[error("the snapshot has {found} cells, where {expected} were expected")]
+    CellCount { expected: usize, found: usize },
+    #[error("the snapshot has more than {MAX_CELLS} cells")]
+    TooLarge,
+}
+
+/// Everything needed to recreate a board, without its history or the generations used to find cycles.
+///
+/// The cells of an unbounded board are stored as runs of cells which are not dead, so patterns far beyond the
+/// viewport are kept without storing the empty space between them.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Snapshot {
+    rule: Rule,
+    #[cfg_attr(feature = "serde", serde(with = "neighbourhood_name"))]
+    neighbourhood: Arc<dyn Neighbourhood>,
+    boundary: Boundary,
+    storage: Storage,
+    generation: u64,
+    width: usize,
+    height: usize,
+    viewport: Coordinates<i64>,
+    /// The cells of a bounded board, row by row, which is empty for an unbounded board
+    cells: Vec<CellState>,
+    /// The cells of an unbounded board which are not dead, row by row, which is empty for a bounded board
+    runs: Vec<Run>,
+    /// The species of each cell, row by row, which is empty unless the rule has more than one species
+    species: Vec<u8>,
+}
+
+/// Cells next to each other in a row of the plane which have the same state.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Run {
+    /// The position of the leftmost cell
+    start: Coordinates<i64>,
+    length: usize,
+    state: CellState,
+}
+
+impl Snapshot {
+    fn of(state: &State) -> Self {
+        let (cells, runs) = match &state.cells {
+            Cells::Unbounded(cells) => (Vec::new(), runs(cells)),
+            _ => (state.cells(), Vec::new()),
+        };
+
+        Self {
+            rule: state.rule,
+            neighbourhood: Arc::clone(&state.neighbourhood),
+            boundary: state.boundary,
+            storage: state.storage(),
+            generation: state.generation,
+            width: state.width,
+            height: state.height,
+            viewport: state.viewport,
+            cells,
+            runs,
+            species: state.species.clone().unwrap_or_default(),
+        }
+    }
+
+    fn into_state(self) -> Result<State, SnapshotError> {
+        let Self {
+            rule,
+            neighbourhood,
+            boundary,
+            storage,
+            generation,
+            width,
+            height,
+            viewport,
+            cells,
+            runs,
+            species,
+        } = self;
+
+        let area = width
+            .checked_mul(height)
+            .filter(|&area| area <= MAX_CELLS)
+            .ok_or(SnapshotError::TooLarge)?;
+
+        let states = cells.iter().chain(runs.iter().map(|run| &run.state));
+        if let Some(&state) = states.clone().find(|&&state| !is_valid(state, rule)) {
+            return Err(SnapshotError::Invalid(match state {
+                CellState::Dying(_) => "dying stage",
+                _ => "cell",
+            }));
+        }
+
+        let board = if storage == Storage::Unbounded {
+            if !cells.is_empty() {
+                return Err(SnapshotError::Invalid("unbounded cells"));
+            }
+
+            let mut length = 0_usize;
+            for run in &runs {
+                length = length
+                    .checked_add(run.length)
+                    .filter(|&length| length <= MAX_CELLS)
+                    .ok_or(SnapshotError::TooLarge)?;
+
+                // The length is at most the maximum number of cells, so it fits in an i64
+                #[allow(clippy::cast_possible_wrap)]
+                if run.start.x.checked_add(run.length as i64).is_none() {
+                    return Err(SnapshotError::Invalid("run"));
+                }
+            }
+
+            State::empty(width, height)
+        } else {
+            if !runs.is_empty() {
+                return Err(SnapshotError::Invalid("bounded cells"));
+            }
+            if cells.len() != area {
+                return Err(SnapshotError::CellCount {
+                    expected: area,
+                    found: cells.len(),
+                });
+            }
+
+            State::from_cells(width, height, cells)
+        };
+
+        // Boards under a rule with more than one species always store the species of every cell
+        let species_count = if rule.species() > 1 { area } else { 0 };
+        if species.len() != species_count {
+            return Err(SnapshotError::CellCount {
+                expected: species_count,
+                found: species.len(),
+            });
+        }
+        if species.iter().any(|&species| species >= rule.species()) {
+            return Err(SnapshotError::Invalid("species"));
+        }
+
+        let mut state = board
+            .with_rule(rule)
+            .with_boundary(boundary)
+            .with_neighbourhood(neighbourhood)
+            .with_viewport(viewport)
+            .with_storage(storage)
+            .with_generation(generation);
+
+        // Rules where cells are born with no alive neighbours can't be unbounded
+        if state.storage() != storage {
+            return Err(SnapshotError::Invalid("storage"));
+        }
+
+        if rule.species() > 1 {
+            state.species = Some(species);
+        }
+
+        if let Cells::Unbounded(chunked) = &mut state.cells {
+            for Run {
+                start,
+                length,
+                state,
+            } in runs
+            {
+                for position in 0..length {
+                    #[allow(clippy::cast_possible_wrap)]
+                    chunked.set(
+                        Coordinates {
+                            y: start.y,
+                            x: start.x + position as i64,
+                        },
+                        state,
+                    );
+                }
+            }
+        }
+
+        Ok(state)
+    }
+}
+
+/// Finds the runs of cells on a plane which are not dead, ordered row by row.
+fn runs(cells: &ChunkedCells) -> Vec<Run> {
+    let mut occupied = cells.occupied().collect::<Vec<_>>();
+    occupied.sort_unstable_by_key(|&(Coordinates { y, x }, _)| (y, x));
+
+    let mut runs = Vec::<Run>::new();
+    for (position, state) in occupied {
+        match runs.last_mut() {
+            #[allow(clippy::cast_possible_wrap)]
+            Some(run)
+                if run.state == state
+                    && run.start.y == position.y
+                    && run.start.x + run.length as i64 == position.x =>
+            {
+                run.length += 1;
+            }
+            _ => runs.push(Run {
+                start: position,
+                length: 1,
+                state,
+            }),
+        }
+    }
+
+    runs
+}
+
+/// Gets the byte a cell's state is stored as.
+fn state_byte(cell: CellState) -> u8 {
+    match cell {
+        CellState::Dead => 0,
+        CellState::Alive => 1,
+        CellState::Dying(stage) => stage.saturating_add(1),
+    }
+}
+
+/// Gets the state of a cell from the byte it is stored as.
+fn byte_state(byte: u8) -> CellState {
+    match byte {
+        0 => CellState::Dead,
+        1 => CellState::Alive,
+        stage => CellState::Dying(stage - 1),
+    }
+}
+
+/// Finds whether a cell can be in a board with a rule, as a rule only has so many stages of dying.
+fn is_valid(cell: CellState, rule: Rule) -> bool {
+    match cell {
+        CellState::Dying(stage) => (1..rule.states() - 1).contains(&stage),
+        _ => true,
+    }
+}
+
+impl State {
+    /// Writes the board as a compact snapshot, holding its rule, neighbourhood, boundary, generation, cells and
+    /// species.
+    ///
+    /// Runs of cells with the same state are stored together, so snapshots of sparse boards are small enough to be
+    /// shared in a URL once encoded. Earlier generations are not stored, so a restored board can't be rewound past
+    /// the generation it was saved at.
+    #[must_use]
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        let snapshot = Snapshot::of(self);
+        let mut bytes = MAGIC.to_vec();
+
+        for text in [
+            snapshot.rule.to_string(),
+            snapshot.neighbourhood.to_string(),
+        ] {
+            write_unsigned(&mut bytes, text.len() as u64);
+            bytes.extend(text.as_bytes());
+        }
+
+        bytes.push(match snapshot.boundary {
+            Boundary::Toroidal => 0,
+            Boundary::Dead => 1,
+            Boundary::Reflective => 2,
+            Boundary::KleinBottle => 3,
+        });
+        bytes.push(match snapshot.storage {
+            Storage::Bytes => 0,
+            Storage::Packed => 1,
+            Storage::Unbounded => 2,
+        });
+
+        write_unsigned(&mut bytes, snapshot.generation);
+        write_unsigned(&mut bytes, snapshot.width as u64);
+        write_unsigned(&mut bytes, snapshot.height as u64);
+        write_signed(&mut bytes, snapshot.viewport.y);
+        write_signed(&mut bytes, snapshot.viewport.x);
+
+        // Each run of an unbounded board is placed relative to the run before it, or the viewport for the first run
+        write_unsigned(&mut bytes, snapshot.runs.len() as u64);
+        let mut previous = snapshot.viewport;
+        for run in snapshot.runs {
+            write_signed(&mut bytes, run.start.y.wrapping_sub(previous.y));
+            write_signed(&mut bytes, run.start.x.wrapping_sub(previous.x));
+            write_unsigned(&mut bytes, run.length as u64);
+            bytes.push(state_byte(run.state));
+            previous = run.start;
+        }
+
+        // Bounded boards always store exactly the cells of the board, followed by their species when the rule has
+        // more than one
+        write_runs(&mut bytes, snapshot.cells.into_iter().map(state_byte));
+        write_runs(&mut bytes, snapshot.species);
+
+        bytes
+    }
+
+    /// Reads a board from a snapshot written by [`State::to_snapshot`].
+    ///
+    /// # Errors
+    /// When the bytes are not a snapshot, or are a snapshot which has been cut short or corrupted.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(SnapshotError::Magic);
+        }
+
+        let rule = reader.text()?.parse::<Rule>()?;
+        let neighbourhood = parse_neighbourhood(reader.text()?)?;
+
+        let boundary = match reader.byte()? {
+            0 => Boundary::Toroidal,
+            1 => Boundary::Dead,
+            2 => Boundary::Reflective,
+            3 => Boundary::KleinBottle,
+            _ => return Err(SnapshotError::Invalid("boundary")),
+        };
+        let storage = match reader.byte()? {
+            0 => Storage::Bytes,
+            1 => Storage::Packed,
+            2 => Storage::Unbounded,
+            _ => return Err(SnapshotError::Invalid("storage")),
+        };
+
+        let generation = reader.unsigned()?;
+        let width = reader.size()?;
+        let height = reader.size()?;
+        let viewport = Coordinates {
+            y: reader.signed()?,
+            x: reader.signed()?,
+        };
+
+        // The size is checked before any cells are read, so a corrupted size can't allocate too much memory
+        let expected = width
+            .checked_mul(height)
+            .filter(|&area| area <= MAX_CELLS)
+            .ok_or(SnapshotError::TooLarge)?;
+
+        let mut runs = Vec::new();
+        let mut previous = viewport;
+        for _ in 0..reader.size()? {
+            let start = Coordinates {
+                y: previous.y.wrapping_add(reader.signed()?),
+                x: previous.x.wrapping_add(reader.signed()?),
+            };
+            runs.push(Run {
+                start,
+                length: reader.size()?,
+                state: byte_state(reader.byte()?),
+            });
+            previous = start;
+        }
+
+        // An unbounded board stores no cells other than its runs
+        let expected = if storage == Storage::Unbounded {
+            0
+        } else {
+            expected
+        };
+
+        let cells = reader.runs(expected)?.into_iter().map(byte_state).collect();
+        let species = reader.runs(if rule.species() > 1 { expected } else { 0 })?;
+
+        if !reader.bytes.is_empty() {
+            return Err(SnapshotError::TrailingBytes);
+        }
+
+        Snapshot {
+            rule,
+            neighbourhood,
+            boundary,
+            storage,
+            generation,
+            width,
+            height,
+            viewport,
+            cells,
+            runs,
+            species,
+        }
+        .into_state()
+    }
+}
+
+/// Writes bytes as runs of the same byte, each stored as its length and then the byte.
+fn write_runs(bytes: &mut Vec<u8>, values: impl IntoIterator<Item = u8>) {
+    let mut values = values.into_iter().peekable();
+    while let Some(value) = values.next() {
+        let mut run = 1;
+        while values.next_if_eq(&value).is_some() {
+            run += 1;
+        }
+
+        write_unsigned(bytes, run);
+        bytes.push(value);
+    }
+}
+
+/// Writes a number in as few bytes as possible, seven bits at a time, where the top bit of each byte is set when
+/// another byte follows.
+fn write_unsigned(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        #[allow(clippy::cast_possible_truncation)]
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Writes a number which may be negative, interleaving positive and negative numbers so small numbers of either
+/// sign are short.
+fn write_signed(bytes: &mut Vec<u8>, value: i64) {
+    #[allow(clippy::cast_sign_loss)]
+    write_unsigned(bytes, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+/// Reads the parts of a snapshot in order, failing when the snapshot ends early.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < length {
+            return Err(SnapshotError::Truncated);
+        }
+
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn unsigned(&mut self) -> Result<u64, SnapshotError> {
+        let mut value = 0_u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(SnapshotError::Invalid("number"))
+    }
+
+    fn signed(&mut self) -> Result<i64, SnapshotError> {
+        let value = self.unsigned()?;
+
+        #[allow(clippy::cast_possible_wrap)]
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn size(&mut self) -> Result<usize, SnapshotError> {
+        usize::try_from(self.unsigned()?).map_err(|_| SnapshotError::Invalid("size"))
+    }
+
+    /// Reads bytes written by [`write_runs`], which must be exactly `expected` bytes once expanded.
+    fn runs(&mut self, expected: usize) -> Result<Vec<u8>, SnapshotError> {
+        let mut values = Vec::new();
+        while !self.bytes.is_empty() && values.len() < expected {
+            let run = self.size()?;
+            let value = self.byte()?;
+
+            if run > expected - values.len() {
+                return Err(SnapshotError::CellCount {
+                    expected,
+                    found: values.len().saturating_add(run),
+                });
+            }
+            values.extend(std::iter::repeat(value).take(run));
+        }
+
+        if values.len() != expected {
+            return Err(SnapshotError::CellCount {
+                expected,
+                found: values.len(),
+            });
+        }
+
+        Ok(values)
+    }
+
+    fn text(&mut self) -> Result<&'a str, SnapshotError> {
+        let length = self.size()?;
+        std::str::from_utf8(self.take(length)?).map_err(|_| SnapshotError::Invalid("name"))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for State {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Snapshot::of(self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for State {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Snapshot::deserialize(deserializer)?
+            .into_state()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Stores a neighbourhood by its name, as written by its `Display` implementation.
+#[cfg(feature = "serde")]
+mod neighbourhood_name {
+    use crate::neighbourhood::{parse_neighbourhood, Neighbourhood};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::sync::Arc;
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn serialize<S: Serializer>(
+        neighbourhood: &Arc<dyn Neighbourhood>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(neighbourhood)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Arc<dyn Neighbourhood>, D::Error> {
+        parse_neighbourhood(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
@@ -2,8 +2,10 @@ use super::*;
 
 #[test]
 fn cells_die_underpopulation() {
-    let mut state = State {
-        cells: vec![
+    let mut state = State::from_cells(
+        3,
+        3,
+        vec![
             // row 0
             CellState::Dead,
             CellState::Dead,
@@ -17,14 +19,12 @@ fn cells_die_underpopulation() {
             CellState::Dead,
             CellState::Dead,
         ],
-        width: 3,
-        height: 3,
-    };
+    );
 
     state.next();
 
     assert_eq!(
-        state.cells,
+        state.cells(),
         vec![
             CellState::Dead,
             CellState::Dead,
@@ -43,8 +43,10 @@ fn cells_die_underpopulation() {
 
 #[test]
 fn cells_die_overpopulation() {
-    let mut state = State {
-        cells: vec![
+    let mut state = State::from_cells(
+        3,
+        3,
+        vec![
             // row 0
             CellState::Alive,
             CellState::Alive,
@@ -58,14 +60,12 @@ fn cells_die_overpopulation() {
             CellState::Alive,
             CellState::Alive,
         ],
-        width: 3,
-        height: 3,
-    };
+    );
 
     state.next();
 
     assert_eq!(
-        state.cells,
+        state.cells(),
         vec![
             CellState::Dead,
             CellState::Dead,
@@ -99,13 +99,1168 @@ fn cells_stay_alive() {
         CellState::Alive,
     ];
 
-    let mut state = State {
-        cells: stable_state.clone(),
-        width: 3,
-        height: 3,
-    };
+    let mut state = State::from_cells(3, 3, stable_state.clone());
 
     state.next();
 
-    assert_eq!(state.cells, stable_state);
+    assert_eq!(state.cells(), stable_state);
+}
+
+#[test]
+fn seeds_cells_always_die() {
+    let mut state = State::from_cells(
+        4,
+        4,
+        vec![
+            // row 0
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            // row 1
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Alive,
+            CellState::Dead,
+            // row 2
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            // row 3
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+        ],
+    )
+    .with_rule(Rule::SEEDS);
+
+    state.next();
+
+    assert_eq!(
+        state.cells(),
+        vec![
+            // row 0
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Alive,
+            CellState::Dead,
+            // row 1
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            // row 2
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Alive,
+            CellState::Dead,
+            // row 3
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+        ]
+    );
+}
+
+#[test]
+fn highlife_births_with_six_neighbours() {
+    let cells = vec![
+        // row 0
+        CellState::Alive,
+        CellState::Alive,
+        CellState::Alive,
+        // row 1
+        CellState::Alive,
+        CellState::Alive,
+        CellState::Alive,
+        // row 2
+        CellState::Dead,
+        CellState::Dead,
+        CellState::Dead,
+    ];
+
+    let mut conway = State::from_cells(3, 3, cells.clone());
+    let mut high_life = State::from_cells(3, 3, cells).with_rule(Rule::HIGH_LIFE);
+
+    conway.next();
+    high_life.next();
+
+    // Through wrapping, every dead cell has 6 alive neighbours and every alive cell has 5
+    assert_eq!(conway.cells(), [CellState::Dead; 9]);
+    assert_eq!(high_life.cells()[..6], [CellState::Dead; 6]);
+    assert_eq!(high_life.cells()[6..], [CellState::Alive; 3]);
+}
+
+/// A 4x4 board with alive cells in the top right, bottom left and bottom row.
+fn boundary_test_state(boundary: Boundary) -> State {
+    State::from_cells(
+        4,
+        4,
+        vec![
+            // row 0
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Alive,
+            // row 1
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            // row 2
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            // row 3
+            CellState::Alive,
+            CellState::Alive,
+            CellState::Dead,
+            CellState::Dead,
+        ],
+    )
+    .with_boundary(boundary)
+}
+
+/// Counts the alive neighbours of the top left corner, right edge, bottom right corner and top edge.
+fn boundary_neighbour_counts(boundary: Boundary) -> [usize; 4] {
+    let state = boundary_test_state(boundary);
+
+    [(0, 0), (3, 1), (3, 3), (2, 0)]
+        .map(|(x, y)| state.get_alive_neighbours_count(Coordinates { y, x }))
+}
+
+#[test]
+fn toroidal_boundary_wraps() {
+    assert_eq!(boundary_neighbour_counts(Boundary::Toroidal), [3, 1, 2, 2]);
+}
+
+#[test]
+fn dead_boundary_has_no_neighbours_beyond_edges() {
+    assert_eq!(boundary_neighbour_counts(Boundary::Dead), [0, 1, 0, 1]);
+}
+
+#[test]
+fn reflective_boundary_mirrors_edges() {
+    assert_eq!(
+        boundary_neighbour_counts(Boundary::Reflective),
+        [0, 2, 0, 2]
+    );
+}
+
+#[test]
+fn klein_bottle_boundary_flips_when_wrapping_vertically() {
+    assert_eq!(
+        boundary_neighbour_counts(Boundary::KleinBottle),
+        [2, 1, 2, 3]
+    );
+}
+
+#[test]
+fn gliders_disappear_at_dead_boundary() {
+    let mut state = State::from_cells(
+        4,
+        4,
+        vec![
+            // row 0
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Dead,
+            CellState::Dead,
+            // row 1
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Dead,
+            // row 2
+            CellState::Alive,
+            CellState::Alive,
+            CellState::Alive,
+            CellState::Dead,
+            // row 3
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+        ],
+    )
+    .with_boundary(Boundary::Dead);
+
+    for _ in 0..16 {
+        state.next();
+    }
+
+    // The glider becomes a block in the bottom right corner
+    assert_eq!(
+        state.cells(),
+        vec![
+            // row 0
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            // row 1
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Dead,
+            // row 2
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Alive,
+            // row 3
+            CellState::Dead,
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Alive,
+        ]
+    );
+}
+
+#[test]
+fn seeded_states_are_reproducible() {
+    let [first, second, other] = [1, 1, 2].map(|seed| State::from_seed(16, 16, seed, 0.5).cells());
+
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+}
+
+#[test]
+fn seeded_state_density() {
+    assert!(State::from_seed(8, 8, 0, 0.0)
+        .cells()
+        .iter()
+        .all(|&state| state == CellState::Dead));
+    assert!(State::from_seed(8, 8, 0, 1.0)
+        .cells()
+        .iter()
+        .all(|&state| state == CellState::Alive));
+}
+
+#[test]
+fn empty_state_is_dead() {
+    let state = State::empty(5, 4);
+
+    assert_eq!(state.cells(), vec![CellState::Dead; 20]);
+    assert_eq!((state.width(), state.height()), (5, 4));
+}
+
+#[test]
+#[should_panic(expected = "must have 9 cells")]
+fn cells_must_fill_board() {
+    let _ = State::from_cells(3, 3, vec![CellState::Alive; 8]);
+}
+
+#[test]
+fn packed_storage_matches_bytes() {
+    for (width, height) in [(1, 1), (3, 5), (64, 4), (70, 6), (130, 3)] {
+        for boundary in [
+            Boundary::Toroidal,
+            Boundary::Dead,
+            Boundary::Reflective,
+            Boundary::KleinBottle,
+        ] {
+            for rule in [
+                Rule::CONWAY,
+                Rule::HIGH_LIFE,
+                Rule::SEEDS,
+                Rule::DAY_AND_NIGHT,
+            ] {
+                let state = || {
+                    State::from_seed(width, height, 7, 0.4)
+                        .with_rule(rule)
+                        .with_boundary(boundary)
+                };
+                let mut bytes = state();
+                let mut packed = state().with_storage(Storage::Packed);
+
+                for _ in 0..8 {
+                    bytes.step();
+                    packed.step();
+
+                    assert_eq!(
+                        bytes.cells(),
+                        packed.cells(),
+                        "{width}x{height} board with {rule} and {boundary} boundary"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn packed_storage_can_be_edited() {
+    let mut state = State::empty(70, 2).with_storage(Storage::Packed);
+
+    assert_eq!(
+        state.replace_at_index(69, CellState::Alive),
+        Some(CellState::Dead)
+    );
+    assert_eq!(state.at_index(69), Some(CellState::Alive));
+    assert_eq!(state.replace_at_index(140, CellState::Alive), None);
+    assert_eq!(state.at_index(140), None);
+
+    let state = state.with_storage(Storage::Bytes);
+    assert_eq!(state.at_index(69), Some(CellState::Alive));
+    assert_eq!(
+        state
+            .cells()
+            .iter()
+            .filter(|&&cell| cell == CellState::Alive)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn packed_frames_mark_changes() {
+    let mut state = State::from_cells(
+        3,
+        3,
+        vec![
+            // row 0
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Dead,
+            // row 1
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Dead,
+            // row 2
+            CellState::Dead,
+            CellState::Alive,
+            CellState::Dead,
+        ],
+    )
+    .with_boundary(Boundary::Dead)
+    .with_storage(Storage::Packed);
+
+    let changed = state
+        .next()
+        .unwrap()
+        .into_iter()
+        .filter(|cell| cell.needs_rerender)
+        .map(|cell| (cell.coordinates.x, cell.coordinates.y))
+        .collect::<Vec<_>>();
+
+    assert_eq!(changed, [(1, 0), (0, 1), (2, 1), (1, 2)]);
+}
+
+fn blinker(storage: Storage) -> State {
+    let mut state = State::empty(5, 5)
+        .with_boundary(Boundary::Dead)
+        .with_storage(storage);
+
+    for x in 1..4 {
+        state.replace_at_index(2 * 5 + x, CellState::Alive);
+    }
+
+    state
+}
+
+fn diff_coordinates(frame: Frame) -> Vec<(i32, i32)> {
+    assert_eq!(frame.kind(), FrameKind::Diff);
+
+    frame
+        .into_iter()
+        .map(|cell| (cell.coordinates.x, cell.coordinates.y))
+        .collect()
+}
+
+#[test]
+fn diff_frames_only_hold_changes() {
+    for storage in [Storage::Bytes, Storage::Packed] {
+        let mut state = blinker(storage);
+        state.next_frame(FrameKind::Full);
+
+        assert_eq!(
+            diff_coordinates(state.next_frame(FrameKind::Diff)),
+            [(2, 1), (1, 2), (3, 2), (2, 3)],
+            "{storage:?} storage"
+        );
+        assert_eq!(
+            diff_coordinates(state.next_frame(FrameKind::Diff)),
+            [(2, 1), (1, 2), (3, 2), (2, 3)],
+            "{storage:?} storage"
+        );
+    }
+}
+
+#[test]
+fn diff_frames_include_edits() {
+    for storage in [Storage::Bytes, Storage::Packed] {
+        let mut state = State::empty(5, 5).with_storage(storage);
+        state.next_frame(FrameKind::Full);
+        assert!(diff_coordinates(state.next_frame(FrameKind::Diff)).is_empty());
+
+        // A lone cell is drawn, then dies in the following step
+        state.replace_at_index(6, CellState::Alive);
+        assert_eq!(
+            diff_coordinates(state.next_frame(FrameKind::Diff)),
+            [(1, 1)],
+            "{storage:?} storage"
+        );
+        assert_eq!(state.at_index(6), Some(CellState::Dead));
+
+        // Edits to a still board wake it up again
+        for index in [6, 7, 8] {
+            state.replace_at_index(index, CellState::Alive);
+        }
+        state.step();
+        assert_eq!(
+            state
+                .cells()
+                .iter()
+                .filter(|&&cell| cell == CellState::Alive)
+                .count(),
+            3
+        );
+        assert_eq!(state.at_index(2), Some(CellState::Alive));
+    }
+}
+
+#[test]
+fn generations_cells_decay_before_dying() {
+    let mut state = State::empty(4, 1)
+        .with_rule(Rule::BRIANS_BRAIN)
+        .with_boundary(Boundary::Dead);
+    state.replace_at_index(0, CellState::Alive);
+    state.replace_at_index(2, CellState::Alive);
+
+    state.step();
+    assert_eq!(
+        state.cells(),
+        [
+            CellState::Dying(1),
+            CellState::Alive,
+            CellState::Dying(1),
+            CellState::Dead,
+        ]
+    );
+
+    // Dying cells are not counted as neighbours, so nothing is born next to them
+    state.step();
+    assert_eq!(
+        state.cells(),
+        [
+            CellState::Dead,
+            CellState::Dying(1),
+            CellState::Dead,
+            CellState::Dead,
+        ]
+    );
+}
+
+#[test]
+fn generations_rules_are_stored_as_bytes() {
+    let state = State::empty(4, 4).with_storage(Storage::Packed);
+    assert_eq!(state.storage(), Storage::Packed);
+
+    let state = state.with_rule(Rule::STAR_WARS);
+    assert_eq!(state.storage(), Storage::Bytes);
+    assert_eq!(
+        state.with_storage(Storage::Packed).storage(),
+        Storage::Bytes
+    );
+}
+
+#[test]
+fn population_and_bounding_box() {
+    for storage in [Storage::Bytes, Storage::Packed] {
+        let state = blinker(storage);
+
+        assert_eq!(state.population(), 3);
+        assert_eq!(
+            state.bounding_box(),
+            Some(BoundingBox {
+                top_left: Coordinates { y: 2, x: 1 },
+                width: 3,
+                height: 1,
+            })
+        );
+        assert_eq!(State::empty(5, 5).bounding_box(), None);
+    }
+}
+
+#[test]
+fn generations_are_counted() {
+    let mut state = State::new(8, 8);
+    assert_eq!(state.generation(), 0);
+
+    state.step();
+    state.next();
+    assert_eq!(state.generation(), 2);
+}
+
+#[test]
+fn settled_boards_are_detected() {
+    for storage in [Storage::Bytes, Storage::Packed] {
+        let mut state = blinker(storage);
+        assert_eq!(state.status(), Status::Running);
+
+        state.step();
+        assert_eq!(state.status(), Status::Running);
+        state.step();
+        assert_eq!(state.status(), Status::Oscillator { period: 2 });
+
+        // Editing the board into a block starts the search again
+        state.replace_at_index(13, CellState::Dead);
+        for index in [6, 7] {
+            state.replace_at_index(index, CellState::Alive);
+        }
+        assert_eq!(state.status(), Status::Running);
+        state.step();
+        assert_eq!(state.status(), Status::StillLife, "{storage:?} storage");
+
+        assert_eq!(
+            State::empty(3, 3).with_storage(storage).status(),
+            Status::Extinct
+        );
+    }
+}
+
+#[test]
+fn boards_with_dying_cells_are_not_extinct() {
+    for storage in [Storage::Bytes, Storage::Unbounded] {
+        let mut state = State::empty(5, 5)
+            .with_rule(Rule::BRIANS_BRAIN)
+            .with_storage(storage);
+        state.replace_at_index(12, CellState::Alive);
+
+        // The cell has no neighbours, so it starts dying, and is only dead a generation later
+        state.step();
+        assert_eq!(state.population(), 0);
+        assert_eq!(state.status(), Status::Running, "{storage:?} storage");
+
+        state.step();
+        assert_eq!(state.status(), Status::Extinct, "{storage:?} storage");
+    }
+}
+
+#[test]
+fn history_steps_back() {
+    for storage in [Storage::Bytes, Storage::Packed] {
+        let mut state = State::from_seed(16, 16, 3, 0.4)
+            .with_storage(storage)
+            .with_history(1 << 20);
+        let mut boards = vec![state.cells()];
+
+        for _ in 0..10 {
+            state.step();
+            boards.push(state.cells());
+        }
+
+        assert!(state.step_back());
+        assert_eq!(state.generation(), 9);
+        assert_eq!(state.cells(), boards[9], "{storage:?} storage");
+
+        assert!(state.jump_to(2));
+        assert_eq!(state.cells(), boards[2], "{storage:?} storage");
+        assert!(state.jump_to(7));
+        assert_eq!(state.cells(), boards[7], "{storage:?} storage");
+
+        assert!(state.jump_to(0));
+        assert!(!state.step_back());
+        assert_eq!(state.cells(), boards[0], "{storage:?} storage");
+    }
+}
+
+#[test]
+fn history_undoes_edits() {
+    let mut state = blinker(Storage::Bytes).with_history(1 << 20);
+    let before = state.cells();
+
+    state.step();
+    state.replace_at_index(0, CellState::Alive);
+    state.step();
+    state.replace_at_index(24, CellState::Alive);
+
+    assert!(state.step_back());
+    assert_eq!(state.generation(), 1);
+    assert_eq!(state.at_index(0), Some(CellState::Dead));
+    assert_eq!(state.at_index(24), Some(CellState::Dead));
+
+    assert!(state.step_back());
+    assert_eq!(state.cells(), before);
+}
+
+#[test]
+fn history_undoes_species() {
+    let mut state = State::from_seed(8, 8, 2, 0.5)
+        .with_rule(Rule::QUAD_LIFE)
+        .with_random_species(3)
+        .with_history(1 << 20);
+    let species = |state: &State| {
+        (0..state.width() * state.height())
+            .map(|index| state.species_at(index))
+            .collect::<Vec<_>>()
+    };
+    let before = species(&state);
+
+    state.step();
+    let stepped = species(&state);
+    state.set_species(0, state.species_at(0) + 1);
+    state.step();
+
+    assert!(state.step_back());
+    assert_eq!(species(&state), stepped);
+    assert!(state.step_back());
+    assert_eq!(species(&state), before);
+}
+
+#[test]
+fn history_is_bounded() {
+    // A blinker changes 4 cells each generation, so only a few generations fit
+    let delta_size = 4 * std::mem::size_of::<(usize, CellState, u8)>();
+    let mut state = blinker(Storage::Bytes).with_history(delta_size * 3);
+
+    for _ in 0..10 {
+        state.step();
+    }
+
+    assert_eq!(state.earliest_generation(), 7);
+    assert!(!state.jump_to(6));
+    assert_eq!(state.generation(), 10);
+    assert!(state.jump_to(7));
+    assert!(!state.step_back());
+
+    assert!(!State::empty(3, 3).step_back());
+}
+
+#[test]
+fn history_counts_edits_made_between_steps() {
+    let delta_size = 4 * std::mem::size_of::<(usize, CellState, u8)>();
+    let mut state = blinker(Storage::Bytes).with_history(delta_size * 3);
+    for _ in 0..3 {
+        state.step();
+    }
+    assert_eq!(state.earliest_generation(), 0);
+
+    // Two edits take the place of half of the oldest generation
+    state.replace_at_index(0, CellState::Alive);
+    state.replace_at_index(0, CellState::Dead);
+    assert_eq!(state.earliest_generation(), 1);
+
+    // Editing without stepping eventually forgets every generation
+    for _ in 0..100 {
+        state.clear();
+        state.randomise(1, 0.5);
+    }
+    assert_eq!(state.earliest_generation(), 3);
+    assert!(!state.step_back());
+}
+
+#[test]
+fn setting_the_generation_forgets_the_history() {
+    let mut state = blinker(Storage::Bytes).with_history(1 << 20);
+    for _ in 0..5 {
+        state.step();
+    }
+
+    let mut state = state.with_generation(0);
+    assert_eq!(state.earliest_generation(), 0);
+    assert!(!state.step_back());
+
+    state.step();
+    assert!(state.step_back());
+    assert_eq!(state.generation(), 0);
+}
+
+fn born_around_single_cell(
+    width: usize,
+    height: usize,
+    neighbourhood: Arc<dyn Neighbourhood>,
+) -> Vec<(i32, i32)> {
+    let mut state = State::empty(width, height)
+        .with_rule(Rule::new([1], []))
+        .with_boundary(Boundary::Dead)
+        .with_neighbourhood(neighbourhood);
+    state.replace_at_index(width * height / 2, CellState::Alive);
+
+    state.step();
+
+    state
+        .cells()
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, cell)| cell == CellState::Alive)
+        .map(|(index, _)| {
+            let Coordinates { y, x } = state.get_coordinates(index);
+            (x, y)
+        })
+        .collect()
+}
+
+#[test]
+fn neighbourhoods_decide_births() {
+    use crate::neighbourhood::{Hexagonal, Moore, Triangular, VonNeumann};
+
+    assert_eq!(
+        born_around_single_cell(3, 3, Arc::new(VonNeumann::default())),
+        [(1, 0), (0, 1), (2, 1), (1, 2)]
+    );
+    assert_eq!(
+        born_around_single_cell(3, 3, Arc::new(Hexagonal)),
+        [(0, 0), (1, 0), (0, 1), (2, 1), (1, 2), (2, 2)]
+    );
+    assert_eq!(
+        born_around_single_cell(3, 3, Arc::new(Triangular)),
+        [(0, 1), (2, 1), (1, 2)]
+    );
+    assert_eq!(
+        born_around_single_cell(5, 5, Arc::new(Moore::new(2))).len(),
+        24
+    );
+}
+
+#[test]
+fn sparse_steps_match_full_steps_for_every_neighbourhood() {
+    for neighbourhood in ["moore", "moore:2", "von-neumann:2", "hex", "triangular"] {
+        for boundary in [
+            Boundary::Toroidal,
+            Boundary::Dead,
+            Boundary::Reflective,
+            Boundary::KleinBottle,
+        ] {
+            let neighbourhood = crate::neighbourhood::parse_neighbourhood(neighbourhood).unwrap();
+            let rule = Rule::new([2, 5, 6], [1, 3, 4]);
+            let mut sparse = State::from_seed(9, 7, 5, 0.3)
+                .with_rule(rule)
+                .with_boundary(boundary)
+                .with_neighbourhood(Arc::clone(&neighbourhood));
+
+            for _ in 0..6 {
+                // A new board has no record of which cells changed, so every cell is stepped
+                let mut full = State::from_cells(9, 7, sparse.cells())
+                    .with_rule(rule)
+                    .with_boundary(boundary)
+                    .with_neighbourhood(Arc::clone(&neighbourhood));
+
+                sparse.step();
+                full.step();
+
+                assert_eq!(
+                    sparse.cells(),
+                    full.cells(),
+                    "{neighbourhood} neighbourhood with {boundary} boundary"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn other_neighbourhoods_are_stored_as_bytes() {
+    let state = State::empty(4, 4)
+        .with_storage(Storage::Packed)
+        .with_neighbourhood(Arc::new(crate::neighbourhood::Hexagonal));
+
+    assert_eq!(state.storage(), Storage::Bytes);
+}
+
+#[test]
+fn unbounded_boards_match_bounded_boards_away_from_the_edges() {
+    for neighbourhood in ["moore", "moore:2", "hex", "triangular"] {
+        let neighbourhood = crate::neighbourhood::parse_neighbourhood(neighbourhood).unwrap();
+        let rule = Rule::new([3], [2, 3]).with_states(3);
+
+        // A soup in the middle of the board can't reach the edges within a few generations
+        let soup = State::from_seed(8, 8, 7, 0.4).cells();
+        let mut bounded = State::empty(40, 40)
+            .with_rule(rule)
+            .with_boundary(Boundary::Dead)
+            .with_neighbourhood(Arc::clone(&neighbourhood));
+        for (index, &state) in soup.iter().enumerate() {
+            bounded.replace_at_index((16 + index / 8) * 40 + 16 + index % 8, state);
+        }
+
+        let mut unbounded = State::from_cells(40, 40, bounded.cells())
+            .with_rule(rule)
+            .with_neighbourhood(Arc::clone(&neighbourhood))
+            .with_viewport(Coordinates { y: -100, x: 50 })
+            .with_storage(Storage::Unbounded);
+        assert_eq!(unbounded.storage(), Storage::Unbounded);
+
+        for _ in 0..6 {
+            bounded.step();
+            unbounded.step();
+
+            assert_eq!(
+                unbounded.cells(),
+                bounded.cells(),
+                "{neighbourhood} neighbourhood"
+            );
+        }
+    }
+}
+
+#[test]
+fn unbounded_boards_grow_beyond_the_viewport() {
+    // A glider travelling up and to the left
+    let mut state = State::empty(6, 6).with_storage(Storage::Unbounded);
+    for index in [0, 1, 2, 6, 13] {
+        state.replace_at_index(index + 2 * 6 + 2, CellState::Alive);
+    }
+
+    for _ in 0..4 * 30 {
+        state.step();
+    }
+
+    assert_eq!(state.population(), 5);
+    assert!(state.cells().iter().all(|&state| state == CellState::Dead));
+
+    let bounding_box = state.bounding_box().unwrap();
+    assert_eq!(
+        bounding_box,
+        BoundingBox {
+            top_left: Coordinates { y: -28, x: -28 },
+            width: 3,
+            height: 3,
+        }
+    );
+
+    state.set_viewport(Coordinates { y: -30, x: -30 });
+    assert_eq!(
+        state
+            .cells()
+            .iter()
+            .filter(|&&state| state == CellState::Alive)
+            .count(),
+        5
+    );
+    assert_eq!(
+        state.window(Coordinates { y: -28, x: -28 }, 3, 3),
+        [0, 1, 2, 3, 4, 5, 6, 7, 8]
+            .map(|index| CellState::from([0, 1, 2, 3, 7].contains(&index)))
+            .to_vec()
+    );
+}
+
+#[test]
+fn moving_the_viewport_rerenders_every_cell() {
+    let mut state = blinker(Storage::Unbounded);
+    state.step();
+    state.set_viewport(Coordinates { y: 1, x: 1 });
+
+    assert_eq!(state.next_frame(FrameKind::Diff).into_iter().count(), 25);
+}
+
+#[test]
+fn births_from_nothing_are_stored_as_bytes() {
+    let state = State::empty(4, 4)
+        .with_rule(Rule::new([0], []))
+        .with_storage(Storage::Unbounded);
+
+    assert_eq!(state.storage(), Storage::Bytes);
+}
+
+/// Checks that a board restored from a snapshot matches the original, and steps the same way.
+fn assert_restores(mut state: State, mut restored: State) {
+    assert_eq!(restored.storage(), state.storage());
+    assert_eq!(restored.rule(), state.rule());
+    assert_eq!(restored.boundary(), state.boundary());
+    assert_eq!(
+        restored.neighbourhood().to_string(),
+        state.neighbourhood().to_string()
+    );
+    assert_eq!(restored.viewport(), state.viewport());
+
+    let species = |state: &State| {
+        (0..state.width() * state.height())
+            .map(|index| state.species_at(index))
+            .collect::<Vec<_>>()
+    };
+
+    for _ in 0..8 {
+        assert_eq!(restored.generation(), state.generation());
+        assert_eq!(restored.cells(), state.cells());
+        assert_eq!(species(&restored), species(&state));
+        assert_eq!(restored.bounding_box(), state.bounding_box());
+
+        state.step();
+        restored.step();
+    }
+}
+
+#[test]
+fn snapshots_restore_boards() {
+    let mut brians_brain = State::from_seed(12, 9, 3, 0.4)
+        .with_rule(Rule::BRIANS_BRAIN)
+        .with_boundary(Boundary::KleinBottle)
+        .with_neighbourhood(Arc::new(neighbourhood::Hexagonal));
+    brians_brain.step();
+    brians_brain.step();
+
+    let mut quad_life = State::from_seed(10, 10, 4, 0.5)
+        .with_rule(Rule::QUAD_LIFE)
+        .with_random_species(4);
+    quad_life.step();
+
+    for state in [
+        blinker(Storage::Bytes),
+        blinker(Storage::Packed),
+        State::from_seed(70, 3, 1, 0.5).with_generation(1 << 40),
+        brians_brain,
+        quad_life,
+    ] {
+        let restored = State::from_snapshot(&state.to_snapshot()).unwrap();
+        assert_restores(state, restored);
+    }
+}
+
+#[test]
+fn snapshots_keep_cells_beyond_the_viewport() {
+    let mut state = State::empty(8, 8).with_storage(Storage::Unbounded);
+    for index in [1, 10, 16, 17, 18] {
+        state.replace_at_index(index, CellState::Alive);
+    }
+    for _ in 0..100 {
+        state.step();
+    }
+    state.set_viewport(Coordinates { y: -3, x: 2 });
+
+    let restored = State::from_snapshot(&state.to_snapshot()).unwrap();
+    assert_restores(state, restored);
+}
+
+#[test]
+fn snapshots_of_sparse_boards_are_small() {
+    let state = State::empty(200, 200);
+
+    assert!(state.to_snapshot().len() < 32);
+}
+
+#[test]
+fn snapshots_of_unbounded_boards_only_store_cells_which_are_not_dead() {
+    let mut state = State::empty(4, 4).with_storage(Storage::Unbounded);
+    for viewport in [
+        Coordinates { y: 0, x: 0 },
+        Coordinates {
+            y: 1 << 40,
+            x: -(1 << 40),
+        },
+    ] {
+        state.set_viewport(viewport);
+        for index in [4, 5, 6] {
+            state.replace_at_index(index, CellState::Alive);
+        }
+    }
+
+    let snapshot = state.to_snapshot();
+    assert!(snapshot.len() < 80);
+    assert_restores(state, State::from_snapshot(&snapshot).unwrap());
+}
+
+#[test]
+fn corrupted_snapshots_are_rejected() {
+    let snapshot = blinker(Storage::Bytes).to_snapshot();
+
+    assert_eq!(
+        State::from_snapshot(&snapshot[..snapshot.len() - 1]).err(),
+        Some(SnapshotError::Truncated)
+    );
+    assert_eq!(
+        State::from_snapshot(&[snapshot.as_slice(), &[1, 0]].concat()).err(),
+        Some(SnapshotError::TrailingBytes)
+    );
+    assert_eq!(
+        State::from_snapshot(b"GIF89a").err(),
+        Some(SnapshotError::Magic)
+    );
+}
+
+#[test]
+fn oversized_snapshots_are_rejected() {
+    // A 2x2 board is stored as its size, viewport and number of unbounded runs, then one run of 4 dead cells
+    let snapshot = State::empty(2, 2).to_snapshot();
+    let (header, rest) = snapshot.split_at(snapshot.len() - 7);
+    assert_eq!(rest, [2, 2, 0, 0, 0, 4, 0]);
+
+    // 2^20 by 2^20 cells
+    let huge = [0x80, 0x80, 0x40];
+    assert_eq!(
+        State::from_snapshot(&[header, &huge, &huge, &[0, 0, 0, 1, 0]].concat()).err(),
+        Some(SnapshotError::TooLarge)
+    );
+    assert_eq!(
+        State::from_snapshot(&[header, &[2, 2, 0, 0, 0], &[0xff; 9], &[1, 1]].concat()).err(),
+        Some(SnapshotError::CellCount {
+            expected: 4,
+            found: usize::MAX,
+        })
+    );
+
+    // An unbounded board with a run of 2^60 cells
+    let header = [&header[..header.len() - 2], &[2, 0]].concat();
+    assert_eq!(
+        State::from_snapshot(
+            &[
+                header.as_slice(),
+                &[2, 2, 0, 0, 1, 0, 0],
+                &[0x80; 8],
+                &[0x10, 1]
+            ]
+            .concat()
+        )
+        .err(),
+        Some(SnapshotError::TooLarge)
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn boards_can_be_serialised() {
+    let mut state = blinker(Storage::Packed).with_rule(Rule::HIGH_LIFE);
+    state.step();
+
+    let json = serde_json::to_string(&state).unwrap();
+    assert!(json.contains(r#""rule":"B36/S23""#), "{json}");
+
+    let restored = serde_json::from_str::<State>(&json).unwrap();
+    assert_restores(state, restored);
+}
+
+#[test]
+fn resizing_crops_and_pads_boards() {
+    for storage in [Storage::Bytes, Storage::Packed] {
+        let mut state = blinker(storage).with_history(1 << 10);
+        state.step();
+
+        state.resize(3, 7);
+        assert_eq!((state.width(), state.height()), (3, 7));
+        assert_eq!(state.storage(), storage);
+        assert_eq!(
+            state.cells(),
+            [
+                [0, 0, 0],
+                [0, 0, 1],
+                [0, 0, 1],
+                [0, 0, 1],
+                [0, 0, 0],
+                [0, 0, 0],
+                [0, 0, 0],
+            ]
+            .concat()
+            .into_iter()
+            .map(|alive| CellState::from(alive == 1))
+            .collect::<Vec<_>>(),
+            "{storage:?} storage"
+        );
+
+        // The earlier generations were a different size, so they can't be stepped back to
+        assert!(!state.step_back());
+
+        // Every cell is rendered again, as each may have moved
+        assert_eq!(state.next_frame(FrameKind::Diff).into_iter().count(), 21);
+    }
+}
+
+#[test]
+fn resizing_unbounded_boards_keeps_every_cell() {
+    let mut state = blinker(Storage::Unbounded);
+    let before = state.cells();
+
+    state.resize(2, 2);
+    assert_eq!(state.cells(), [CellState::Dead; 4]);
+
+    state.resize(5, 5);
+    assert_eq!(state.cells(), before);
+}
+
+#[test]
+fn newborns_take_the_species_of_most_parents() {
+    let mut state = blinker(Storage::Packed).with_rule(Rule::IMMIGRATION);
+    assert_eq!(state.storage(), Storage::Bytes);
+
+    state.set_species(2 * 5 + 1, 1);
+    state.set_species(2 * 5 + 3, 1);
+
+    let frame = state.next_frame(FrameKind::Full);
+    let species_at = |x, y| {
+        frame
+            .buffer
+            .iter()
+            .find(|cell| cell.coordinates == Coordinates { y, x })
+            .map(|cell| cell.species)
+    };
+
+    assert_eq!(species_at(2, 1), Some(1));
+    assert_eq!(species_at(2, 3), Some(1));
+    // The middle cell survives, so it keeps its species
+    assert_eq!(species_at(2, 2), Some(0));
+}
+
+#[test]
+fn boards_only_settle_once_their_species_repeat() {
+    let mut state = blinker(Storage::Bytes).with_rule(Rule::IMMIGRATION);
+    for index in [2 * 5 + 2, 2 * 5 + 3] {
+        state.set_species(index, 1);
+    }
+
+    // The cells repeat after two generations, but the first cell is only reborn as the second species then
+    state.step();
+    state.step();
+    assert_eq!(state.species_at(2 * 5 + 1), 1);
+    assert_eq!(state.status(), Status::Running);
+
+    state.step();
+    state.step();
+    assert_eq!(state.status(), Status::Oscillator { period: 2 });
+}
+
+#[test]
+fn editing_species_unsettles_boards() {
+    let mut state = blinker(Storage::Bytes).with_rule(Rule::IMMIGRATION);
+    state.step();
+    state.step();
+    assert_eq!(state.status(), Status::Oscillator { period: 2 });
+
+    state.set_species(2 * 5 + 2, 1);
+    assert_eq!(state.status(), Status::Running);
+
+    state.step();
+    state.step();
+    let state = state.with_random_species(5);
+    assert_eq!(state.status(), Status::Running);
+}
+
+#[test]
+fn quad_life_newborns_take_the_missing_species() {
+    let mut state = blinker(Storage::Bytes).with_rule(Rule::QUAD_LIFE);
+    state.set_species(2 * 5 + 2, 3);
+    state.set_species(2 * 5 + 3, 1);
+
+    state.step();
+
+    assert_eq!(state.species_at(5 + 2), 2);
+    assert_eq!(state.species_at(3 * 5 + 2), 2);
+
+    // Going back to a rule with one species forgets them
+    let state = state.with_rule(Rule::CONWAY);
+    assert_eq!(state.species_at(5 + 2), 0);
+}
+
+#[test]
+fn random_species_are_reproducible() {
+    let state = || {
+        State::from_seed(8, 8, 1, 0.5)
+            .with_rule(Rule::QUAD_LIFE)
+            .with_random_species(7)
+    };
+
+    let species = |state: State| {
+        (0..64)
+            .map(|index| state.species_at(index))
+            .collect::<Vec<_>>()
+    };
+
+    let first = species(state());
+    assert!(first.iter().all(|&species| species < 4));
+    assert!(first.iter().any(|&species| species != first[0]));
+    assert_eq!(first, species(state()));
 }
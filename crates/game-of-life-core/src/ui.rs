@@ -1,4 +1,4 @@
-use crate::state::{Frame, State};
+use crate::state::{Frame, FrameKind, State};
 use std::marker::PhantomData;
 
 pub trait RendererBackend<E>: Sized
@@ -13,6 +13,13 @@ where
     /// When the backed experiences an error, such as failing to render to stdout, it will error.
     fn render(&mut self, state: Frame) -> Result<(), E>;
 
+    /// The kind of frame the backend wants to render next, which defaults to frames holding every cell.
+    ///
+    /// Backends which keep what they have already drawn can ask for diff frames, which only hold changed cells.
+    fn frame_kind(&self) -> FrameKind {
+        FrameKind::Full
+    }
+
     /// Creates a renderer given some config.
     ///
     /// # Errors
@@ -43,14 +50,59 @@ where
         }
     }
 
+    /// Splits the renderer into the state and the backend, such as to drive the state with a
+    /// [`Runner`](crate::runner::Runner) and render its frames with the backend.
+    pub fn into_parts(self) -> (State, B) {
+        (self.state, self.backend)
+    }
+
+    /// Gets the state being rendered, such as to check whether it has settled.
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
     /// Renders the next state.
     ///
     /// # Errors
     /// When it fails to a render, it will error.
-    #[allow(clippy::missing_panics_doc)]
     pub fn render_next_state(&mut self) -> Result<(), E> {
         let Self { state, backend, .. } = self;
-        backend.render(state.next().unwrap())
+        let kind = backend.frame_kind();
+        backend.render(state.next_frame(kind))
+    }
+
+    /// Steps the state back a generation and renders it, which needs the state to have a history.
+    ///
+    /// Returns whether the state could be stepped back.
+    ///
+    /// # Errors
+    /// When it fails to a render, it will error.
+    pub fn step_back(&mut self) -> Result<bool, E> {
+        let Self { state, backend, .. } = self;
+
+        if !state.step_back() {
+            return Ok(false);
+        }
+
+        backend.render(state.frame())?;
+        Ok(true)
+    }
+
+    /// Steps the state backwards or forwards to a generation and renders it.
+    ///
+    /// Returns whether the generation could be reached, as earlier generations may have been forgotten.
+    ///
+    /// # Errors
+    /// When it fails to a render, it will error.
+    pub fn jump_to(&mut self, generation: u64) -> Result<bool, E> {
+        let Self { state, backend, .. } = self;
+
+        if !state.jump_to(generation) {
+            return Ok(false);
+        }
+
+        backend.render(state.frame())?;
+        Ok(true)
     }
 }
 
@@ -1,9 +1,15 @@
 #![warn(clippy::pedantic)]
 
-use clap::Parser;
-use game_of_life_core::prelude::*;
+use clap::{Parser, ValueEnum};
+use game_of_life_core::{
+    age::Ages,
+    census::Census,
+    hashlife::{HashLife, UnsupportedRuleError},
+    neighbourhood::{parse_neighbourhood, Tiling},
+    prelude::*,
+};
 use gif::{Encoder, Frame};
-use std::{fs::File, path::PathBuf};
+use std::{fs::File, path::PathBuf, sync::Arc};
 use thiserror::Error;
 
 #[cfg(debug_assertions)]
@@ -13,28 +19,162 @@ pub const DEFAULT_OUTPUT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/outp
 pub const DEFAULT_OUTPUT_PATH: &str = "output.gif";
 
 struct GifBackend {
-    width: u16,
-    height: u16,
+    /// The width of the board (in cells)
+    width: usize,
+    /// The height of the board (in cells)
+    height: usize,
+    /// The shape cells are drawn in
+    tiling: Tiling,
+    /// How many pixels down each cell is drawn with, which is also how many across for square cells
+    cell_size: usize,
+    /// The index in the palette of the color of the lines drawn around each cell, when there are grid lines
+    grid: Option<u8>,
+    image_width: u16,
+    image_height: u16,
     frame_delay: u16,
+    /// How alive cells are colored, which is by their age when there is an old color
+    shades: Shades,
     encoder: Encoder<File>,
 }
 
+/// How many shades of the alive color the palette has, where each is used for cells of some age.
+struct Shades {
+    /// The ages of the cells, when alive cells fade towards the old color as they age
+    ages: Option<Ages>,
+    /// How many shades there are, where the last is used for cells of this age or older
+    count: u8,
+}
+
 #[derive(Parser, Debug)]
 struct GifBackendConfig {
     #[clap(short, long, default_value = DEFAULT_OUTPUT_PATH)]
     path: PathBuf,
+    /// The width of the board (in cells)
     #[clap(long, default_value_t = 256)]
     width: u16,
+    /// The height of the board (in cells)
     #[clap(long, default_value_t = 256)]
     height: u16,
+    /// How many pixels across and down each cell is drawn with, so small boards can be seen more easily
+    ///
+    /// Hexagons are drawn twice as wide, and triangles twice as wide at their base.
+    #[clap(long, value_parser = clap::value_parser!(u16).range(1..), default_value_t = 1)]
+    cell_size: u16,
+    /// Draw a line one pixel wide around every cell, in this color
+    #[clap(long, value_parser = parse_hex_color)]
+    grid_color: Option<[u8; 3]>,
     #[clap(short = 'd', long, default_value_t = 10)]
     frame_delay: u16,
     #[clap(short = 'c', long, default_value_t = 512)]
     frame_count: usize,
-    #[clap(long, value_parser = parse_hex_color, default_value = "ffffff")]
-    alive_color: [u8; 3],
-    #[clap(long, value_parser = parse_hex_color, default_value = "000000")]
-    dead_color: [u8; 3],
+    /// The colors used when they are not given, where every palette other than classic fades alive cells as they age
+    #[clap(long, value_enum, default_value_t = Palette::default())]
+    palette: Palette,
+    /// The color of an alive cell, which is taken from the palette by default
+    #[clap(long, value_parser = parse_hex_color)]
+    alive_color: Option<[u8; 3]>,
+    /// The color of a dead cell, which is taken from the palette by default
+    #[clap(long, value_parser = parse_hex_color)]
+    dead_color: Option<[u8; 3]>,
+    /// The color of a cell which has just started dying, under a rule with more than two states (such as B2/S/C3),
+    /// which is taken from the palette by default
+    ///
+    /// Later stages of dying fade towards the dead color.
+    #[clap(long, value_parser = parse_hex_color)]
+    dying_color: Option<[u8; 3]>,
+    /// The color alive cells fade towards as they age, which colors cells by how many generations they have been
+    /// alive, and is taken from the palette by default
+    #[clap(long, value_parser = parse_hex_color)]
+    old_color: Option<[u8; 3]>,
+    /// How many generations alive cells take to fade to the old color, where longer fades have fewer shades once
+    /// the 256 colors a gif can have run out
+    #[clap(long, value_parser = clap::value_parser!(u16).range(1..), default_value_t = 16)]
+    max_age: u16,
+    /// The rule used to update the board, as a rulestring in B/S notation (such as B36/S23, or B2/S/C3 with dying states)
+    ///
+    /// Defaults to the rule of the pattern, or B3/S23 when there is no pattern.
+    #[clap(long)]
+    rule: Option<Rule>,
+    /// How cells beyond the edges of the board are treated (torus, dead, mirror or klein)
+    #[clap(long, default_value_t = Boundary::default())]
+    boundary: Boundary,
+    /// Simulate an unbounded plane, which the gif is a window onto, so patterns can grow beyond its edges
+    ///
+    /// The boundary is ignored.
+    #[clap(long)]
+    unbounded: bool,
+    /// Which cells are the neighbours of each cell (moore, von-neumann, hex or triangular), where moore and
+    /// von-neumann can have a larger radius such as moore:2
+    #[clap(long, value_parser = parse_neighbourhood)]
+    neighbourhood: Option<Arc<dyn Neighbourhood>>,
+    /// The seed used to randomly fill the board, so that a run can be reproduced (random by default)
+    #[clap(long)]
+    seed: Option<u64>,
+    /// The proportion of cells that are alive at the start, between 0 and 1
+    #[clap(long, value_parser = parse_density, default_value_t = 0.5)]
+    density: f64,
+    /// A pattern file to place in the middle of an empty board (in RLE or plaintext format)
+    #[clap(long)]
+    pattern: Option<PathBuf>,
+    /// Jump ahead by this many generations before the first frame, using the hashlife algorithm
+    ///
+    /// Hashlife simulates an unbounded plane, so the boundary is ignored while skipping. Cells which leave the gif
+    /// while skipping are dropped, unless the board is unbounded.
+    #[clap(long, default_value_t = 0)]
+    skip: u64,
+}
+
+/// A set of colors for the cells, which are used unless colors are given on the command line.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug, Default)]
+enum Palette {
+    /// White cells on black, with red dying cells
+    #[default]
+    Classic,
+    /// Yellow cells which burn down to red, on a dark red background
+    Fire,
+    /// Pale blue cells which deepen to navy, on a dark blue background
+    Ocean,
+    /// Light green cells which darken to moss, on a dark green background
+    Forest,
+}
+
+/// The colors of a palette, where alive cells only fade as they age when there is an old color.
+struct Colors {
+    alive: [u8; 3],
+    dead: [u8; 3],
+    dying: [u8; 3],
+    old: Option<[u8; 3]>,
+}
+
+impl Palette {
+    fn colors(self) -> Colors {
+        match self {
+            Self::Classic => Colors {
+                alive: [0xff, 0xff, 0xff],
+                dead: [0x00, 0x00, 0x00],
+                dying: [0xff, 0x00, 0x00],
+                old: None,
+            },
+            Self::Fire => Colors {
+                alive: [0xff, 0xf0, 0x80],
+                dead: [0x18, 0x04, 0x00],
+                dying: [0x80, 0x20, 0x00],
+                old: Some([0xc0, 0x20, 0x00]),
+            },
+            Self::Ocean => Colors {
+                alive: [0x8f, 0xe3, 0xff],
+                dead: [0x00, 0x10, 0x20],
+                dying: [0x20, 0x60, 0x80],
+                old: Some([0x10, 0x50, 0xa0]),
+            },
+            Self::Forest => Colors {
+                alive: [0xb0, 0xff, 0x60],
+                dead: [0x0a, 0x1a, 0x0a],
+                dying: [0x80, 0x60, 0x20],
+                old: Some([0x20, 0x60, 0x20]),
+            },
+        }
+    }
 }
 
 #[derive(Error, Debug)]
@@ -48,19 +188,78 @@ enum ParseHexColorError {
 fn parse_hex_color(input: &str) -> Result<[u8; 3], ParseHexColorError> {
     if input.len() != 6 {
         return Err(ParseHexColorError::Length);
-    };
+    }
 
     let bytes = u32::from_str_radix(input, 16)?.to_le_bytes();
 
     Ok([bytes[2], bytes[1], bytes[0]])
 }
 
+/// Mixes two colors, where an amount of 0 is the first color and 1 is the second.
+fn mix(from: [u8; 3], to: [u8; 3], amount: f32) -> [u8; 3] {
+    let mut color = from;
+    for (channel, to_channel) in color.iter_mut().zip(to) {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        {
+            *channel = (f32::from(*channel) * (1.0 - amount) + f32::from(to_channel) * amount)
+                .round() as u8;
+        }
+    }
+    color
+}
+
+/// Builds the palette, where index 0 is dead, the shades of alive follow from the alive color towards the old color,
+/// then each stage of dying follows, fading from the dying color towards the dead color, and the grid color is last.
+fn palette(colors: &Colors, shades: u8, states: u8, grid_color: Option<[u8; 3]>) -> Vec<u8> {
+    let dying_stages = states.saturating_sub(2);
+
+    let alive_colors = (0..shades).map(|shade| match colors.old {
+        Some(old) if shades > 1 => mix(colors.alive, old, f32::from(shade) / f32::from(shades - 1)),
+        _ => colors.alive,
+    });
+
+    let dying_colors = (0..dying_stages).map(|stage| {
+        mix(
+            colors.dying,
+            colors.dead,
+            f32::from(stage) / f32::from(dying_stages + 1),
+        )
+    });
+
+    std::iter::once(colors.dead)
+        .chain(alive_colors)
+        .chain(dying_colors)
+        .chain(grid_color)
+        .flatten()
+        .collect()
+}
+
+#[derive(Error, Debug)]
+#[error("density must be between 0 and 1")]
+struct ParseDensityError;
+
+fn parse_density(input: &str) -> Result<f64, ParseDensityError> {
+    input
+        .parse()
+        .ok()
+        .filter(|density| (0.0..=1.0).contains(density))
+        .ok_or(ParseDensityError)
+}
+
 #[derive(Error, Debug)]
 enum GifBackendError {
     #[error("failed to write to output")]
     IO(#[from] std::io::Error),
     #[error("failed encoding")]
     Encoding(#[from] gif::EncodingError),
+    #[error("failed to read pattern")]
+    Pattern(#[from] game_of_life_core::pattern::PatternError),
+    #[error("failed to skip generations")]
+    Skip(#[from] UnsupportedRuleError),
+    #[error(
+        "the gif would be {0}x{1} pixels, but gifs can be at most 65535 pixels across and down"
+    )]
+    TooLarge(usize, usize),
 }
 
 impl GifBackend {
@@ -69,35 +268,205 @@ impl GifBackend {
             path,
             width,
             height,
+            cell_size,
+            grid_color,
             frame_delay,
+            palette: palette_colors,
             alive_color,
             dead_color,
+            dying_color,
+            old_color,
+            max_age,
             ..
         }: GifBackendConfig,
+        state: &State,
     ) -> Result<Self, GifBackendError> {
+        let (width, height, cell_size) = (
+            usize::from(width),
+            usize::from(height),
+            usize::from(cell_size),
+        );
+
+        // Each grid line is a pixel wide, and there is one more line than there are cells
+        let line = usize::from(grid_color.is_some());
+        let stride = cell_size + line;
+        let tiling = state.neighbourhood().tiling();
+        let (image_width, image_height) = (
+            match tiling {
+                Tiling::Square => width * stride + line,
+                // Each row is half a hexagon to the left of the row above, so the rows lean to the left
+                Tiling::Hexagonal => (height.saturating_sub(1) + 2 * width) * stride + line,
+                // Triangles overlap their neighbours by half their base, and there are no lines between columns
+                Tiling::Triangular => (width + 1) * cell_size,
+            },
+            height * stride + line,
+        );
+        let (Ok(image_width), Ok(image_height)) =
+            (u16::try_from(image_width), u16::try_from(image_height))
+        else {
+            return Err(GifBackendError::TooLarge(image_width, image_height));
+        };
+
+        let defaults = palette_colors.colors();
+        let colors = Colors {
+            alive: alive_color.unwrap_or(defaults.alive),
+            dead: dead_color.unwrap_or(defaults.dead),
+            dying: dying_color.unwrap_or(defaults.dying),
+            old: old_color.or(defaults.old),
+        };
+
+        // A gif has at most 256 colors, which are shared between dead, alive, dying and the grid
+        let states = state.rule().states();
+        let shades = if colors.old.is_some() {
+            let spare =
+                256 - 1 - usize::from(states.saturating_sub(2)) - usize::from(grid_color.is_some());
+            u8::try_from(usize::from(max_age).min(spare).max(1)).unwrap_or(u8::MAX)
+        } else {
+            1
+        };
+        let shades = Shades {
+            ages: colors.old.map(|_| Ages::new(state)),
+            count: shades,
+        };
+
+        // The grid color comes after every color of a cell
+        let grid = grid_color.map(|_| shades.count.saturating_add(states.saturating_sub(1)));
+
         log::info!("Opening gif file");
         let file = File::create(path).unwrap();
         log::info!("Creating gif encoder");
-        let mut encoder = Encoder::new(file, width, height, &[dead_color, alive_color].concat())?;
+        let mut encoder = Encoder::new(
+            file,
+            image_width,
+            image_height,
+            &palette(&colors, shades.count, states, grid_color),
+        )?;
         encoder.set_repeat(gif::Repeat::Infinite)?;
 
         Ok(Self {
             width,
             height,
+            tiling,
+            cell_size,
+            grid,
+            image_width,
+            image_height,
             frame_delay,
+            shades,
             encoder,
         })
     }
+
+    /// Updates the ages of the cells once the state has changed, when alive cells are colored by their age, so this
+    /// should be called before rendering.
+    fn update_ages(&mut self, state: &State) {
+        if let Some(ages) = &mut self.shades.ages {
+            ages.update(state);
+        }
+    }
+
+    /// Gets the index of a cell's color in the palette built by [`palette`].
+    fn palette_index(&self, index: usize, state: CellState) -> u8 {
+        let Shades { ages, count } = &self.shades;
+
+        match state {
+            CellState::Dead => 0,
+            CellState::Alive => {
+                let age = ages.as_ref().map_or(1, |ages| ages.get(index));
+                let shade = u8::try_from(age.saturating_sub(1)).unwrap_or(u8::MAX);
+                1 + shade.min(count - 1)
+            }
+            CellState::Dying(dying_stage) => count.saturating_add(dying_stage),
+        }
+    }
+
+    /// Draws the cells, given by their indices in the palette, as the pixels of a frame, where each cell is drawn in
+    /// the shape of the tiling with any grid lines between them, and pixels beyond the board are dead.
+    fn pixels(&self, cells: &[u8]) -> Vec<u8> {
+        let has_grid = self.grid.is_some();
+        let grid_index = self.grid.unwrap_or(0);
+
+        let stride = self.cell_size + usize::from(has_grid);
+        let is_line = |pixel: usize| has_grid && pixel % stride == 0;
+        let cell = |pixel: usize| (pixel - usize::from(has_grid)) / stride;
+
+        // Gets the pixel at a column of a row of cells, given how far down the row it is
+        let pixel = |row: usize, row_pixel: usize, x: usize| {
+            let cells = &cells[row * self.width..(row + 1) * self.width];
+
+            match self.tiling {
+                Tiling::Square if is_line(x) => grid_index,
+                Tiling::Square => cells[cell(x)],
+                Tiling::Hexagonal => {
+                    let Some(x) = x.checked_sub((self.height - 1 - row) * stride) else {
+                        return 0;
+                    };
+                    let column = x / (2 * stride);
+
+                    if has_grid && x % (2 * stride) == 0 && column <= self.width {
+                        grid_index
+                    } else {
+                        cells.get(column).copied().unwrap_or(0)
+                    }
+                }
+                Tiling::Triangular => {
+                    // Each strip of pixels as wide as a cell is split by a diagonal into the left half of one
+                    // triangle and the right half of the triangle before it, where the cell at (0, 0) points up
+                    let (strip, across) = (x / self.cell_size, x % self.cell_size);
+                    let (on_edge, is_right) = if (strip + row) % 2 == 0 {
+                        (
+                            across + row_pixel + 1 == self.cell_size,
+                            across + row_pixel + 1 >= self.cell_size,
+                        )
+                    } else {
+                        (across == row_pixel, across >= row_pixel)
+                    };
+
+                    if has_grid && on_edge && strip <= self.width {
+                        grid_index
+                    } else if is_right {
+                        cells.get(strip).copied().unwrap_or(0)
+                    } else {
+                        strip.checked_sub(1).map_or(0, |column| cells[column])
+                    }
+                }
+            }
+        };
+
+        let (image_width, image_height) = (
+            usize::from(self.image_width),
+            usize::from(self.image_height),
+        );
+        let mut pixels = Vec::with_capacity(image_width * image_height);
+
+        for y in 0..image_height {
+            if is_line(y) {
+                pixels.extend(std::iter::repeat(grid_index).take(image_width));
+                continue;
+            }
+
+            let row_pixel = (y - usize::from(has_grid)) % stride;
+            pixels.extend((0..image_width).map(|x| pixel(cell(y), row_pixel, x)));
+        }
+
+        pixels
+    }
 }
 
 impl RendererBackend<GifBackendError> for GifBackend {
     type Config = GifBackendConfig;
 
     fn render(&mut self, state: state::Frame) -> Result<(), GifBackendError> {
+        let cells = state
+            .to_state_iter()
+            .enumerate()
+            .map(|(index, state)| self.palette_index(index, state))
+            .collect::<Vec<_>>();
+
         let mut frame = Frame::from_indexed_pixels(
-            self.width,
-            self.height,
-            &state.to_buffer(|state| u8::from(state == CellState::Alive)),
+            self.image_width,
+            self.image_height,
+            &self.pixels(&cells),
             None,
         );
 
@@ -110,10 +479,46 @@ impl RendererBackend<GifBackendError> for GifBackend {
     }
 
     fn renderer(config: Self::Config) -> Result<Renderer<Self, GifBackendError>, GifBackendError> {
-        Ok(Renderer::new(
-            State::new(config.width.into(), config.height.into()),
-            Self::new(config)?,
-        ))
+        let (width, height) = (config.width.into(), config.height.into());
+
+        let state = if let Some(path) = &config.pattern {
+            log::info!("Reading pattern");
+            let pattern = Pattern::parse(&std::fs::read_to_string(path)?)?;
+            let mut state = State::empty(width, height)
+                .with_rule(config.rule.or(pattern.rule).unwrap_or_default());
+            pattern.stamp_centered(&mut state);
+            state
+        } else {
+            let seed = config.seed.unwrap_or_else(rand::random);
+            log::info!("Using seed {seed}");
+            State::from_seed(width, height, seed, config.density)
+                .with_rule(config.rule.unwrap_or_default())
+        }
+        .with_boundary(config.boundary);
+
+        let state = match &config.neighbourhood {
+            Some(neighbourhood) => state.with_neighbourhood(Arc::clone(neighbourhood)),
+            None => state,
+        };
+
+        let state = if config.unbounded {
+            state.with_storage(state::Storage::Unbounded)
+        } else {
+            state
+        };
+
+        // The board is made unbounded before skipping ahead, so cells which leave the gif while skipping are kept
+        let state = if config.skip > 0 {
+            log::info!("Skipping {} generations", config.skip);
+            let mut hashlife = HashLife::from_state(&state)?;
+            hashlife.step_by(config.skip);
+            hashlife.to_state()
+        } else {
+            state
+        };
+
+        let backend = Self::new(config, &state)?;
+        Ok(Renderer::new(state, backend))
     }
 }
 
@@ -127,13 +532,33 @@ fn main() -> Result<(), GifBackendError> {
 
     let frame_count = config.frame_count;
 
-    let renderer = GifBackend::renderer(config)?;
+    let (mut state, mut backend) = GifBackend::renderer(config)?.into_parts();
 
     log::info!("Rendering frames");
 
-    renderer
-        .take(frame_count)
-        .collect::<Result<(), GifBackendError>>()?;
+    for _ in 0..frame_count {
+        // The ages are updated between stepping and rendering, so each frame is colored by the ages it shows
+        let frame = state.next_frame(backend.frame_kind());
+        backend.update_ages(&state);
+        backend.render(frame)?;
+
+        // Once the board has settled, the gif already loops through every frame it will ever show
+        if state.status().is_settled() {
+            log::info!(
+                "Stopping early, as the board is {} at generation {}",
+                state.status(),
+                state.generation()
+            );
+
+            // The objects left on a settled board can be identified, when the rule is supported
+            if let Ok(census) = Census::take(&state) {
+                if !census.objects().is_empty() {
+                    log::info!("Found these objects:\n{census}");
+                }
+            }
+            break;
+        }
+    }
 
     log::info!("Complete");
 
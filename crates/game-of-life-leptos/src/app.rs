@@ -1,10 +1,18 @@
-use game_of_life_core::{prelude as game_of_life, state::CellState};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    FutureExt, StreamExt,
+};
+use game_of_life_core::{
+    neighbourhood::parse_neighbourhood, prelude as game_of_life, state::CellState,
+};
 use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
-use std::sync::{Arc, Mutex, RwLock};
-#[cfg(target_arch = "wasm32")]
-use wasm_bindgen::prelude::*;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 #[component]
 pub fn App(cx: Scope) -> impl IntoView {
@@ -28,33 +36,52 @@ pub fn App(cx: Scope) -> impl IntoView {
 type CellToggleFunction = Arc<Mutex<Box<dyn FnMut()>>>;
 
 struct StateWrapper {
-    state: Arc<RwLock<game_of_life::State>>,
+    controller: game_of_life::Controller,
     signals: Vec<(
         ReadSignal<CellState>,
         WriteSignal<CellState>,
         CellToggleFunction,
     )>,
+    /// A summary of the generation, population and status of the board
+    summary: (ReadSignal<String>, WriteSignal<String>),
+    /// A link to the board as it was when sharing was last asked for
+    share_link: (ReadSignal<Option<String>>, WriteSignal<Option<String>>),
+    /// Asks for a link to the board, as encoding a snapshot of it is only worth doing when it is shared
+    share_requests: (UnboundedSender<()>, UnboundedReceiver<()>),
+}
+
+fn summary(state: &game_of_life::State) -> String {
+    format!(
+        "Generation {}, {} alive, {}",
+        state.generation(),
+        state.population(),
+        state.status()
+    )
+}
+
+fn share_link(state: &game_of_life::State) -> String {
+    format!("?snapshot={}", URL_SAFE_NO_PAD.encode(state.to_snapshot()))
 }
 
 impl StateWrapper {
-    fn new(cx: Scope, state: game_of_life::State) -> Self {
+    /// Wraps a board, along with the runner which steps it, which starts paused.
+    fn new(cx: Scope, state: game_of_life::State) -> (Self, game_of_life::Runner) {
         let cells = state.cells();
+        let summary = create_signal(cx, summary(&state));
+        let share_link = create_signal(cx, None);
 
-        let state = Arc::new(RwLock::new(state));
+        let runner = game_of_life::Runner::new(state, Duration::from_millis(100))
+            .with_frame_kind(game_of_life::FrameKind::Diff)
+            .paused();
+        let controller = runner.controller();
 
         let signals = create_many_signals(cx, cells)
             .into_iter()
             .enumerate()
             .map(|(index, (cell_state, set_cell_state_internal))| {
-                let state = Arc::clone(&state);
-                let set_cell_state = move || {
-                    let new_cell_state = !cell_state.get();
-                    state
-                        .write()
-                        .unwrap()
-                        .replace_at_index(index, new_cell_state);
-                    set_cell_state_internal.set(new_cell_state);
-                };
+                // The signal is updated once the runner renders the edit
+                let controller = controller.clone();
+                let set_cell_state = move || controller.edit(index, !cell_state.get());
                 (
                     cell_state,
                     set_cell_state_internal,
@@ -63,25 +90,79 @@ impl StateWrapper {
             })
             .collect();
 
-        Self { signals, state }
+        let wrapper = Self {
+            controller,
+            signals,
+            summary,
+            share_link,
+            share_requests: mpsc::unbounded(),
+        };
+
+        (wrapper, runner)
     }
 
-    fn next_state(&mut self) {
-        let Self { state, signals } = self;
-
-        for game_of_life::CellRenderInfo {
-            state: new_state,
-            coordinates,
-            needs_rerender,
-        } in state.write().unwrap().next().unwrap().into_iter()
-        {
-            if !needs_rerender {
-                continue;
+    /// Updates the signals with every frame of the runner, and links to the board whenever sharing is asked for, for
+    /// as long as the page is open.
+    #[allow(dead_code)]
+    async fn run(self, mut runner: game_of_life::Runner) {
+        let Self {
+            signals,
+            summary: (_, set_summary),
+            share_link: (_, set_share_link),
+            share_requests: (_, mut share_requests),
+            ..
+        } = self;
+
+        loop {
+            let frame = futures::select! {
+                frame = runner.next().fuse() => frame,
+                request = share_requests.next() => {
+                    if request.is_some() {
+                        set_share_link.set(Some(share_link(runner.state())));
+                    }
+                    continue;
+                }
+            };
+            let Some(frame) = frame else {
+                break;
+            };
+
+            for game_of_life::CellRenderInfo {
+                state: new_state,
+                coordinates,
+                needs_rerender,
+                ..
+            } in frame
+            {
+                if needs_rerender {
+                    let (_, set_state_internal, _) = signals[coordinates.to_index(20) as usize];
+
+                    set_state_internal.update(|state| *state = new_state);
+                }
             }
 
-            let (_, set_state_internal, _) = signals[coordinates.to_index(20) as usize];
+            set_summary.set(summary(runner.state()));
+        }
+    }
+
+    fn controller(&self) -> game_of_life::Controller {
+        self.controller.clone()
+    }
 
-            set_state_internal.update(|state| *state = new_state);
+    fn summary_signal(&self) -> ReadSignal<String> {
+        self.summary.0
+    }
+
+    fn share_link_signal(&self) -> ReadSignal<Option<String>> {
+        self.share_link.0
+    }
+
+    /// Creates a function which asks for a link to the board as it is now, which the running board then sets.
+    fn sharer(&self) -> impl Fn() + Clone {
+        let share_requests = self.share_requests.0.clone();
+        // The requests are only dropped once the page is closed, so there is nobody to tell
+        move || {
+            let _ = share_requests.unbounded_send(());
         }
     }
 
@@ -95,29 +176,74 @@ impl StateWrapper {
 
 #[component]
 fn HomePage(cx: Scope) -> impl IntoView {
-    let mut state = StateWrapper::new(cx, game_of_life::State::new(20, 20));
-    let cells = state.cell_signals();
+    // The rule, boundary, neighbourhood and seed can be chosen with query parameters, such as `?rule=B36/S23&seed=1`,
+    // and `?unbounded` simulates an unbounded plane which the board is a window onto
+    let query = use_query_map(cx).get();
+    let rule = query
+        .get("rule")
+        .and_then(|rule| rule.parse::<game_of_life::Rule>().ok());
+    let boundary = query
+        .get("boundary")
+        .and_then(|boundary| boundary.parse().ok())
+        .unwrap_or_default();
+    let neighbourhood = query
+        .get("neighbourhood")
+        .and_then(|neighbourhood| parse_neighbourhood(neighbourhood).ok());
+    let unbounded = query.get("unbounded").is_some();
+    let seed = query
+        .get("seed")
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(rand::random);
 
-    let (should_update, set_should_update) = create_signal(cx, false);
+    // A pattern in RLE or plaintext format can also be given, which is placed on an empty board
+    let pattern = query
+        .get("pattern")
+        .and_then(|pattern| pattern.parse::<game_of_life::Pattern>().ok());
 
-    #[allow(unused_variables)]
-    let tick = move || {
-        if should_update.get() {
-            state.next_state();
+    // A shared board is restored from its snapshot, which holds its own rule, boundary and neighbourhood
+    let snapshot = query
+        .get("snapshot")
+        .and_then(|snapshot| URL_SAFE_NO_PAD.decode(snapshot).ok())
+        .and_then(|snapshot| game_of_life::State::from_snapshot(&snapshot).ok())
+        .filter(|state| (state.width(), state.height()) == (20, 20));
+
+    let state = if let Some(state) = snapshot {
+        state
+    } else {
+        let state = if let Some(pattern) = pattern {
+            let mut state = game_of_life::State::empty(20, 20)
+                .with_rule(rule.or(pattern.rule).unwrap_or_default());
+            pattern.stamp_centered(&mut state);
+            state
+        } else {
+            game_of_life::State::from_seed(20, 20, seed, 0.5).with_rule(rule.unwrap_or_default())
+        }
+        .with_boundary(boundary);
+
+        match neighbourhood {
+            Some(neighbourhood) => state.with_neighbourhood(neighbourhood),
+            None => state,
         }
     };
+    let state = if unbounded {
+        state.with_storage(game_of_life::state::Storage::Unbounded)
+    } else {
+        state
+    };
 
+    // Up to 4 MiB of earlier generations are remembered, so the board can be rewound
+    #[allow(unused_variables)]
+    let (state, runner) = StateWrapper::new(cx, state.with_history(4 << 20));
+    let cells = state.cell_signals();
+    let summary = state.summary_signal();
+    let share_link = state.share_link_signal();
+    let share = state.sharer();
+    let controller = state.controller();
+    let (speed, set_speed) = create_signal(cx, 1.0);
+
+    // The board is only stepped in the browser
     #[cfg(target_arch = "wasm32")]
-    {
-        let callback = Closure::wrap(Box::new(tick) as Box<dyn FnMut()>).into_js_value();
-        let interval = std::time::Duration::from_millis(100);
-        window()
-            .set_interval_with_callback_and_timeout_and_arguments_0(
-                callback.as_ref().unchecked_ref(),
-                interval.as_millis().try_into().unwrap_throw(),
-            )
-            .unwrap();
-    }
+    spawn_local(state.run(runner));
 
     view! {
         cx,
@@ -132,6 +258,7 @@ fn HomePage(cx: Scope) -> impl IntoView {
                             <div
                                 class="p-4 w-fit h-fit grid place-items-center rounded-sm transition-colors"
                                 class=("bg-slate-500", move || state.get() == game_of_life::CellState::Dead)
+                                class=("bg-rose-400", move || matches!(state.get(), game_of_life::CellState::Dying(_)))
                                 on:click=move |_| set_state.lock().unwrap()()
                             />
                         }
@@ -141,10 +268,57 @@ fn HomePage(cx: Scope) -> impl IntoView {
             <div class="p-4 grid place-items-center">
                 <button
                     class="p-4 rounded bg-indigo-500 hover:bg-indigo-600 active:opacity-70 text-white shadow transition-colors"
-                    on:click=move |_| set_should_update.update(move |value| *value = !*value)
+                    on:click={
+                        let controller = controller.clone();
+                        move |_| controller.toggle_pause()
+                    }
                 >
                     "Toggle"
                 </button>
+                <button
+                    class="p-4 rounded bg-indigo-500 hover:bg-indigo-600 active:opacity-70 text-white shadow transition-colors"
+                    on:click={
+                        let controller = controller.clone();
+                        move |_| {
+                            controller.pause();
+                            controller.step_back();
+                        }
+                    }
+                >
+                    "Back"
+                </button>
+                <button
+                    class="p-4 rounded bg-indigo-500 hover:bg-indigo-600 active:opacity-70 text-white shadow transition-colors"
+                    on:click={
+                        let controller = controller.clone();
+                        move |_| {
+                            set_speed.update(|speed| *speed /= 2.0);
+                            controller.set_speed(speed.get());
+                        }
+                    }
+                >
+                    "Slower"
+                </button>
+                <button
+                    class="p-4 rounded bg-indigo-500 hover:bg-indigo-600 active:opacity-70 text-white shadow transition-colors"
+                    on:click=move |_| {
+                        set_speed.update(|speed| *speed *= 2.0);
+                        controller.set_speed(speed.get());
+                    }
+                >
+                    "Faster"
+                </button>
+                <button
+                    class="p-4 rounded bg-indigo-500 hover:bg-indigo-600 active:opacity-70 text-white shadow transition-colors"
+                    on:click=move |_| share()
+                >
+                    "Share"
+                </button>
+                {move || share_link.get().map(|link| view! {
+                    cx,
+                    <a class="p-2 text-indigo-600 underline" href=link>"Link to this board"</a>
+                })}
+                <p class="p-2 text-slate-700">{move || summary.get()}</p>
             </div>
         </div>
     }
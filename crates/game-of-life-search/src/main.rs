@@ -0,0 +1,184 @@
+#![warn(clippy::pedantic)]
+
+use clap::{Parser, ValueEnum};
+use game_of_life_core::{
+    census::{Census, UnsupportedRuleError},
+    prelude::*,
+};
+use rayon::prelude::*;
+use search::{Search, Soup};
+use serde::Serialize;
+use std::{fs::File, path::PathBuf, time::Instant};
+use thiserror::Error;
+
+mod search;
+
+/// Searches random soups for rare objects, writing a report of the soups worth a closer look.
+///
+/// Each soup is a square of randomly filled cells on an unbounded plane, which is stepped until it is stable before
+/// its objects are identified. A soup can be watched again from its seed, such as with
+/// `game-of-life-gif --width 16 --height 16 --unbounded --seed <seed>`.
+#[derive(Parser, Debug)]
+struct Args {
+    /// How many soups to search
+    #[clap(short = 'n', long, default_value_t = 1000)]
+    soups: u64,
+    /// The seed of the first soup, where each soup after it uses the next seed (random by default)
+    #[clap(long)]
+    seed: Option<u64>,
+    /// The width and height of each soup (in cells)
+    #[clap(long, default_value_t = 16)]
+    size: usize,
+    /// The proportion of cells that are alive at the start, between 0 and 1
+    #[clap(long, value_parser = parse_density, default_value_t = 0.5)]
+    density: f64,
+    /// The rule used to update the soups, as a rulestring in B/S notation (such as B36/S23)
+    #[clap(long, default_value_t = Rule::default())]
+    rule: Rule,
+    /// The most generations to step a soup while waiting for it to become stable
+    #[clap(long, default_value_t = 10_000)]
+    max_generations: u64,
+    /// Where to write the report
+    #[clap(short, long, default_value = "report.json")]
+    path: PathBuf,
+    #[clap(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// Report every soup, rather than only those with rare objects or which never became stable
+    #[clap(long)]
+    all: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    Json,
+    Csv,
+}
+
+#[derive(Error, Debug)]
+#[error("density must be between 0 and 1")]
+struct ParseDensityError;
+
+fn parse_density(input: &str) -> Result<f64, ParseDensityError> {
+    input
+        .parse()
+        .ok()
+        .filter(|density| (0.0..=1.0).contains(density))
+        .ok_or(ParseDensityError)
+}
+
+#[derive(Error, Debug)]
+enum SearchError {
+    #[error("failed to write report")]
+    IO(#[from] std::io::Error),
+    #[error("failed to write report as JSON")]
+    Json(#[from] serde_json::Error),
+    #[error("failed to write report as CSV")]
+    Csv(#[from] csv::Error),
+    #[error("failed to identify objects")]
+    Census(#[from] UnsupportedRuleError),
+}
+
+/// A soup as a row of a CSV report, which can't hold lists, so the apgcodes are separated by spaces.
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    seed: u64,
+    rule: &'a str,
+    size: usize,
+    density: f64,
+    generations: u64,
+    is_stable: bool,
+    population: usize,
+    rare_objects: String,
+}
+
+impl<'a> From<&'a Soup> for CsvRow<'a> {
+    fn from(soup: &'a Soup) -> Self {
+        Self {
+            seed: soup.seed,
+            rule: &soup.rule,
+            size: soup.size,
+            density: soup.density,
+            generations: soup.generations,
+            is_stable: soup.is_stable,
+            population: soup.population,
+            rare_objects: soup.rare_objects.join(" "),
+        }
+    }
+}
+
+fn write_report(soups: &[Soup], path: PathBuf, format: Format) -> Result<(), SearchError> {
+    match format {
+        Format::Json => serde_json::to_writer_pretty(File::create(path)?, soups)?,
+        Format::Csv => {
+            let mut writer = csv::Writer::from_path(path)?;
+            for soup in soups {
+                writer.serialize(CsvRow::from(soup))?;
+            }
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<(), SearchError> {
+    pretty_env_logger::formatted_builder()
+        .filter_level(log::LevelFilter::Info)
+        .init();
+
+    let Args {
+        soups,
+        seed,
+        size,
+        density,
+        rule,
+        max_generations,
+        path,
+        format,
+        all,
+    } = Args::parse();
+
+    let search = Search {
+        size,
+        density,
+        rule,
+        max_generations,
+    };
+    let first_seed = seed.unwrap_or_else(rand::random);
+
+    // The rule is checked on an empty board, rather than after the first soup has been stepped
+    Census::take(&State::empty(0, 0).with_rule(rule))?;
+
+    log::info!("Searching {soups} soups, starting from seed {first_seed}");
+    let start = Instant::now();
+
+    let mut found = (0..soups)
+        .into_par_iter()
+        .map(|soup| search.run(first_seed.wrapping_add(soup)))
+        .filter(|soup| {
+            soup.as_ref()
+                .map_or(true, |soup| all || soup.is_interesting())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    found.sort_unstable_by_key(|soup| soup.seed);
+
+    log::info!(
+        "Searched {soups} soups in {:.1?}, and found {} worth a closer look",
+        start.elapsed(),
+        found.len()
+    );
+    for soup in found.iter().filter(|soup| soup.is_interesting()) {
+        if soup.is_stable {
+            log::info!("Seed {}: {}", soup.seed, soup.rare_objects.join(" "));
+        } else {
+            log::info!(
+                "Seed {}: not stable after {} generations",
+                soup.seed,
+                soup.generations
+            );
+        }
+    }
+
+    log::info!("Writing report to {}", path.display());
+    write_report(&found, path, format)
+}
@@ -0,0 +1,126 @@
+use game_of_life_core::{
+    census::{Census, UnsupportedRuleError},
+    prelude::*,
+    state::Storage,
+};
+use serde::Serialize;
+
+#[cfg(test)]
+mod tests;
+
+/// The longest period of the population of a board which is recognised as stable.
+const MAX_POPULATION_PERIOD: usize = 60;
+
+/// How many times a period of the population must be seen before a board is treated as stable.
+const POPULATION_REPEATS: usize = 3;
+
+/// How the soups of a search are made and run.
+#[derive(Clone, Copy, Debug)]
+pub struct Search {
+    pub size: usize,
+    pub density: f64,
+    pub rule: Rule,
+    pub max_generations: u64,
+}
+
+/// What a soup turned into, along with everything needed to run it again.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Soup {
+    pub seed: u64,
+    pub rule: String,
+    pub size: usize,
+    pub density: f64,
+    /// The generation the soup became stable at, or the most generations it was stepped when it never did
+    pub generations: u64,
+    pub is_stable: bool,
+    pub population: usize,
+    /// The apgcodes of the objects which have no common name
+    pub rare_objects: Vec<String>,
+}
+
+impl Soup {
+    /// Whether the soup is worth a closer look, as it has rare objects or never became stable.
+    #[must_use]
+    pub fn is_interesting(&self) -> bool {
+        !self.is_stable || !self.rare_objects.is_empty()
+    }
+}
+
+impl Search {
+    /// Fills a soup from a seed on an unbounded plane, steps it until it is stable, and takes a census of its objects.
+    ///
+    /// # Errors
+    /// When the objects can't be identified under the rule.
+    pub fn run(&self, seed: u64) -> Result<Soup, UnsupportedRuleError> {
+        let Self {
+            size,
+            density,
+            rule,
+            max_generations,
+        } = *self;
+
+        let mut state = State::from_seed(size, size, seed, density)
+            .with_rule(rule)
+            .with_storage(Storage::Unbounded);
+
+        let is_stable = stabilise(&mut state, max_generations);
+
+        let mut rare_objects = if is_stable {
+            Census::take(&state)?
+                .objects()
+                .iter()
+                .filter(|object| object.name().is_none())
+                .map(|object| object.apgcode.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        rare_objects.sort_unstable();
+
+        Ok(Soup {
+            seed,
+            rule: rule.to_string(),
+            size,
+            density,
+            generations: state.generation(),
+            is_stable,
+            population: state.population(),
+            rare_objects,
+        })
+    }
+}
+
+/// Steps a board until it is stable, returning whether it became stable within `max_generations`.
+///
+/// A board is stable once it settles, or once its population keeps repeating, which also finds boards with
+/// spaceships flying away.
+pub fn stabilise(state: &mut State, max_generations: u64) -> bool {
+    let mut populations = vec![state.population()];
+
+    loop {
+        if state.status().is_settled() || has_periodic_population(&populations) {
+            return true;
+        }
+        if state.generation() >= max_generations {
+            return false;
+        }
+
+        state.step();
+        populations.push(state.population());
+    }
+}
+
+/// Finds whether the most recent populations repeat with any period, for long enough that it is unlikely to be chance.
+fn has_periodic_population(populations: &[usize]) -> bool {
+    (1..=MAX_POPULATION_PERIOD).any(|period| {
+        let generations = (period * POPULATION_REPEATS).max(MAX_POPULATION_PERIOD);
+
+        populations.len() > generations && {
+            let recent = &populations[populations.len() - generations..];
+            recent
+                .iter()
+                .zip(&recent[period..])
+                .all(|(first, second)| first == second)
+        }
+    })
+}
@@ -0,0 +1,42 @@
+use super::*;
+
+fn search() -> Search {
+    Search {
+        size: 16,
+        density: 0.5,
+        rule: Rule::default(),
+        max_generations: 10_000,
+    }
+}
+
+#[test]
+fn soups_are_reproducible() {
+    assert_eq!(search().run(7).unwrap(), search().run(7).unwrap());
+}
+
+#[test]
+fn soups_become_stable() {
+    let soup = search().run(1).unwrap();
+
+    assert!(soup.is_stable);
+    assert!(soup.generations < 10_000);
+}
+
+#[test]
+fn escaping_spaceships_are_stable() {
+    // A glider never repeats the board, but its population does
+    let mut state = State::empty(8, 8).with_storage(Storage::Unbounded);
+    for index in [1, 10, 16, 17, 18] {
+        state.replace_at_index(index, CellState::Alive);
+    }
+
+    assert!(stabilise(&mut state, 1000));
+    assert!(state.generation() < 100);
+}
+
+#[test]
+fn chaotic_boards_are_not_stable() {
+    let populations = (0..200).map(|generation| generation % 7 + generation / 50);
+
+    assert!(!has_periodic_population(&populations.collect::<Vec<_>>()));
+}
@@ -0,0 +1,62 @@
+use clap::ValueEnum;
+use game_of_life_core::{pattern::Pattern, prelude::*};
+
+/// The format the board is printed in by --print.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PrintFormat {
+    /// The plaintext format, with a row of `.` and `O` for each row of cells
+    Cells,
+    /// Golly's RLE format, which also holds the rule
+    Rle,
+}
+
+/// Steps the board until it has run for some generations, or has settled when `until_stable` is set, whichever comes
+/// first.
+///
+/// With neither, the board is left as it is, and a board which never settles is stepped forever unless the number of
+/// generations is given.
+pub fn run_until(state: &mut State, generations: Option<u64>, until_stable: bool) {
+    if generations.is_none() && !until_stable {
+        return;
+    }
+
+    // The board is stepped one generation at a time, so it stops exactly at the last generation
+    let end = generations.map(|generations| state.generation().saturating_add(generations));
+    while end != Some(state.generation()) && !(until_stable && state.status().is_settled()) {
+        state.step();
+    }
+}
+
+/// Prints the board to stdout, with its generation and status as a comment.
+pub fn print_board(state: &State, format: PrintFormat) {
+    let mut pattern = Pattern::from(state);
+    pattern.comments.push(format!(
+        "Generation {}, {}",
+        state.generation(),
+        state.status()
+    ));
+
+    match format {
+        PrintFormat::Cells => print!("{}", pattern.to_plaintext()),
+        PrintFormat::Rle => print!("{}", pattern.to_rle()),
+    }
+}
+
+#[test]
+fn boards_run_until_stable() {
+    // A small random board, which soon dies out or settles
+    let mut state = State::from_seed(12, 12, 3, 0.4);
+    run_until(&mut state, None, true);
+    assert!(state.status().is_settled());
+
+    let settled_at = state.generation();
+    run_until(&mut state, Some(5), false);
+    assert_eq!(state.generation(), settled_at + 5);
+
+    // The board has already settled, so it isn't stepped again
+    run_until(&mut state, Some(5), true);
+    assert_eq!(state.generation(), settled_at + 5);
+
+    run_until(&mut state, None, false);
+    assert_eq!(state.generation(), settled_at + 5);
+}
@@ -1,12 +1,15 @@
 #![warn(clippy::pedantic)]
 
 use clap::Parser;
-use crossterm::event::{EventStream, KeyCode};
+use crossterm::event::{
+    Event, EventStream, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind,
+};
 use futures::{FutureExt, StreamExt};
-use game_of_life_core::prelude::*;
-use std::time::Duration;
+use game_of_life_core::{census::Census, pattern::Pattern, prelude::*};
+use std::{fs, path::PathBuf, time::Duration};
 use tokio::select;
 
+mod headless;
 mod terminal;
 
 /// An implementation of the game of life in rust.
@@ -16,11 +19,41 @@ mod terminal;
 /// ```sh
 /// game-of-life -r 10 -c 10 -d 100
 /// ```
+///
+/// Press space to pause, n to step forward a generation, b to rewind a generation, + and - to double or halve the
+/// speed, c to clear the board, r to randomly refill it, and escape to exit. Cells can be toggled by clicking them,
+/// and painted by dragging across them.
+///
+/// When the board is larger than the terminal, the arrow keys move the view around it, and [ and ] zoom out and in,
+/// so each cell drawn stands for a square of cells.
+///
+/// Press p to pick a classic pattern, such as a glider gun or a methuselah, from the built-in library, which is
+/// placed centred on the mouse when enter is pressed, or wherever the board is clicked.
+///
+/// With --print, the board is run without drawing it, and printed once it stops, such as to check a pattern in CI
+/// ```sh
+/// game-of-life --pattern glider.rle -r 20 -c 20 --generations 100 --print rle
+/// ```
 #[derive(Parser)]
 struct Args {
     /// The delay to wait before updating the board (in milliseconds)
     #[clap(long, short, default_value_t = 500)]
     delay: u64,
+    /// Exit once the board dies out or settles into a still life or an oscillator
+    #[clap(long)]
+    exit_when_settled: bool,
+    /// Save a snapshot of the board to this file on exit, which can be resumed with --snapshot
+    #[clap(long)]
+    save: Option<PathBuf>,
+    /// Run the board without drawing it, and print it once it stops (as cells or RLE, which is cells by default)
+    #[clap(long, value_enum, num_args = 0..=1, default_missing_value = "cells")]
+    print: Option<headless::PrintFormat>,
+    /// Stop after this many generations, when printing the board
+    #[clap(long, requires = "print")]
+    generations: Option<u64>,
+    /// Stop once the board dies out or settles into a still life or an oscillator, when printing the board
+    #[clap(long, requires = "print")]
+    until_stable: bool,
     #[command(flatten)]
     backend_config: terminal::Config,
 }
@@ -29,31 +62,258 @@ struct Args {
 async fn main() -> crossterm::Result<()> {
     let Args {
         delay,
+        exit_when_settled,
+        save,
+        print,
+        generations,
+        until_stable,
         backend_config,
     } = Args::parse();
 
+    let state = if let Some(format) = print {
+        let mut state = backend_config.state()?;
+        headless::run_until(&mut state, generations, until_stable);
+        headless::print_board(&state, format);
+        state
+    } else {
+        run_interactive(delay, exit_when_settled, backend_config).await?
+    };
+
+    if let Some(path) = save {
+        fs::write(path, state.to_snapshot())?;
+    }
+
+    Ok(())
+}
+
+/// Runs the board in the terminal until escape is pressed, or it settles when `exit_when_settled` is set, and gives
+/// back the board as it was left.
+async fn run_interactive(
+    delay: u64,
+    exit_when_settled: bool,
+    backend_config: terminal::Config,
+) -> crossterm::Result<State> {
+    let density = backend_config.density;
     let mut event_stream = EventStream::new();
 
-    let mut ui = terminal::Backend::renderer(backend_config)?;
+    let (state, mut backend) = terminal::Backend::renderer(backend_config)?.into_parts();
+    let mut runner =
+        Runner::new(state, Duration::from_millis(delay)).with_frame_kind(backend.frame_kind());
 
-    loop {
-        ui.render_next_state()?;
+    let mut settled_report = None;
 
-        let event_listener = event_stream.next().fuse();
-        let sleeper = tokio::time::sleep(Duration::from_millis(delay));
+    // The state cells are set to while the mouse is dragged, which is the state of the first cell clicked
+    let mut painting = None;
+    // The pattern picker, while it is open, and where the mouse last was, which is where patterns are placed
+    let mut picker: Option<terminal::Picker> = None;
+    let mut cursor = None;
 
+    loop {
         select! {
-            event = event_listener => {
+            frame = runner.next() => {
+                let Some(frame) = frame else {
+                    break;
+                };
+                backend.update_ages(runner.state());
+                backend.render(frame)?;
+                backend.render_status(&runner)?;
+                if let Some(picker) = &picker {
+                    backend.render_picker(picker)?;
+                }
+                runner.set_frame_kind(backend.frame_kind());
+
+                let state = runner.state();
+                if exit_when_settled && state.status().is_settled() {
+                    let message = format!(
+                        "The board is {} at generation {}, with {} alive cells",
+                        state.status(),
+                        state.generation(),
+                        state.population()
+                    );
+
+                    // The objects left on a settled board can be identified, when the rule is supported
+                    let census = Census::take(state)
+                        .ok()
+                        .filter(|census| !census.objects().is_empty());
+
+                    settled_report = Some((message, census));
+                    break;
+                }
+            }
+            event = event_stream.next().fuse() => {
                 let Some(event) = event else {
                     continue;
                 };
-                let event = event?;
-                if event == crossterm::event::Event::Key(KeyCode::Esc.into()) {
-                    break
-                };
+                match event? {
+                    Event::Key(KeyEvent { code, .. }) if picker.is_some() => {
+                        if let Some(open) = &mut picker {
+                            if !apply_picker_key(code, open, &mut runner, &backend, cursor) {
+                                close_picker(picker.take(), &mut runner, &mut backend)?;
+                            }
+                        }
+                    }
+                    Event::Key(KeyEvent {
+                        code: KeyCode::Char('p'), ..
+                    }) => {
+                        picker = Some(terminal::Picker::new(runner.is_paused()));
+                        runner.apply(Command::Pause);
+                    }
+                    Event::Key(KeyEvent {
+                        code: KeyCode::Esc, ..
+                    }) => break,
+                    Event::Key(KeyEvent { code, .. }) => {
+                        apply_key(code, &mut runner, &mut backend, density)?;
+                    }
+                    Event::Resize(columns, rows) => backend.resize(columns, rows, runner.state_mut())?,
+                    Event::Mouse(MouseEvent {
+                        kind,
+                        column,
+                        row,
+                        ..
+                    }) => {
+                        cursor = Some((column, row));
+
+                        // While the picker is open, clicking places the pattern instead of toggling a cell
+                        if let (MouseEventKind::Down(MouseButton::Left), Some(open)) = (kind, &picker) {
+                            place(&open.pattern(), &mut runner, &backend, cursor);
+                            close_picker(picker.take(), &mut runner, &mut backend)?;
+                        } else {
+                            paint(kind, backend.cell_at(column, row), &mut runner, &mut painting);
+                        }
+                    }
+                    _ => {}
+                }
+
+                // Pausing and moving the view change the status line, even when no frame is rendered
+                backend.render_status(&runner)?;
+                if let Some(picker) = &picker {
+                    backend.render_picker(picker)?;
+                }
+            }
+        }
+    }
+
+    // The terminal must be restored before anything is printed
+    drop(backend);
+    if let Some((message, census)) = settled_report {
+        println!("{message}");
+        if let Some(census) = census {
+            println!("{census}");
+        }
+    }
+
+    Ok(runner.into_state())
+}
+
+/// Toggles the cell clicked on, and sets the cells dragged across to the state it was toggled to.
+fn paint(
+    kind: MouseEventKind,
+    index: Option<usize>,
+    runner: &mut Runner,
+    painting: &mut Option<CellState>,
+) {
+    let current = index.and_then(|index| runner.state().at_index(index));
+
+    match (kind, index, current) {
+        (MouseEventKind::Down(MouseButton::Left), Some(index), Some(current)) => {
+            *painting = Some(!current);
+            runner.apply(Command::Edit(index, !current));
+        }
+        (MouseEventKind::Drag(MouseButton::Left), Some(index), Some(current)) => {
+            if let Some(painted) = painting.filter(|&painted| painted != current) {
+                runner.apply(Command::Edit(index, painted));
             }
-            _ = sleeper => {}
         }
+        (MouseEventKind::Up(MouseButton::Left), ..) => *painting = None,
+        _ => {}
+    }
+}
+
+/// Applies a key press while the pattern picker is open, returning whether the picker stays open.
+fn apply_picker_key(
+    code: KeyCode,
+    picker: &mut terminal::Picker,
+    runner: &mut Runner,
+    backend: &terminal::Backend,
+    cursor: Option<(u16, u16)>,
+) -> bool {
+    match code {
+        KeyCode::Up => picker.previous(),
+        KeyCode::Down => picker.next(),
+        KeyCode::Enter => {
+            place(&picker.pattern(), runner, backend, cursor);
+            return false;
+        }
+        KeyCode::Esc | KeyCode::Char('p') => return false,
+        _ => {}
     }
+
+    true
+}
+
+/// Closes the pattern picker, resuming the board unless it was paused before the picker was opened.
+fn close_picker(
+    picker: Option<terminal::Picker>,
+    runner: &mut Runner,
+    backend: &mut terminal::Backend,
+) -> crossterm::Result<()> {
+    if picker.is_some_and(|picker| !picker.was_paused()) {
+        runner.apply(Command::Resume);
+    }
+    backend.redraw()
+}
+
+/// Places a pattern centred on the cell under the mouse, or in the middle of the board when the mouse isn't over it.
+fn place(
+    pattern: &Pattern,
+    runner: &mut Runner,
+    backend: &terminal::Backend,
+    cursor: Option<(u16, u16)>,
+) {
+    let (width, height) = (runner.state().width(), runner.state().height());
+    let (x, y) = cursor
+        .and_then(|(column, row)| backend.cell_at(column, row))
+        .map_or((width / 2, height / 2), |index| {
+            (index % width, index / width)
+        });
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let at = Coordinates {
+        y: y as i32 - pattern.height() as i32 / 2,
+        x: x as i32 - pattern.width() as i32 / 2,
+    };
+    runner.stamp(pattern, at);
+}
+
+/// Applies a key press, other than escape, to the runner or the view of the board.
+fn apply_key(
+    code: KeyCode,
+    runner: &mut Runner,
+    backend: &mut terminal::Backend,
+    density: f64,
+) -> crossterm::Result<()> {
+    match code {
+        KeyCode::Char(' ') => runner.apply(Command::TogglePause),
+        KeyCode::Char('n') => {
+            runner.apply(Command::Pause);
+            runner.apply(Command::Step);
+        }
+        KeyCode::Char('b') => {
+            runner.apply(Command::Pause);
+            runner.apply(Command::StepBack);
+        }
+        KeyCode::Left => backend.pan(-1, 0)?,
+        KeyCode::Right => backend.pan(1, 0)?,
+        KeyCode::Up => backend.pan(0, -1)?,
+        KeyCode::Down => backend.pan(0, 1)?,
+        KeyCode::Char('[') => backend.zoom_out()?,
+        KeyCode::Char(']') => backend.zoom_in()?,
+        KeyCode::Char('+') => runner.apply(Command::SetSpeed(runner.speed() * 2.0)),
+        KeyCode::Char('-') => runner.apply(Command::SetSpeed(runner.speed() / 2.0)),
+        KeyCode::Char('c') => runner.apply(Command::Clear),
+        KeyCode::Char('r') => runner.apply(Command::Randomise(rand::random(), density)),
+        _ => {}
+    }
+
     Ok(())
 }
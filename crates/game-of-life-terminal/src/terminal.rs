@@ -1,44 +1,157 @@
-pub use config::Config;
+use camera::Camera;
+use config::ColorWrapper;
+pub use config::{Config, OnResize};
 use crossterm::{
-    cursor::{self, MoveToColumn, MoveToRow},
+    cursor::{self, MoveTo, MoveToColumn, MoveToRow},
     event::{DisableMouseCapture, EnableMouseCapture},
     execute, queue,
-    style::{Color, Print, SetBackgroundColor},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    style::{
+        Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+    },
+    terminal::{
+        disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
 };
+use game_of_life_core::age::Ages;
+use game_of_life_core::neighbourhood::Tiling;
 use game_of_life_core::prelude::*;
-use std::io::{stdout, Stdout, Write};
+pub use library::Picker;
+pub use mode::RenderMode;
+use mode::{most_alive, triangle, DrawnCell};
+use std::{
+    io::{stdout, Stdout, Write},
+    num::NonZeroU16,
+};
 
+mod camera;
 mod config;
+mod library;
+mod mode;
+mod theme;
+
+use theme::Theme;
 
 pub struct Backend {
     terminal: Stdout,
     colors: CellColors,
+    mode: RenderMode,
+    /// The shape cells are drawn in, which is square unless each character draws one cell
+    tiling: Tiling,
+    /// The cells as they were last rendered, so a character which draws several cells can be redrawn when only one
+    /// of them changes
+    cells: Vec<CellState>,
+    /// The species of the cells as they were last rendered
+    species: Vec<u8>,
+    /// The ages of the cells, when alive cells are colored by their age
+    ages: Option<Ages>,
+    /// The characters which draw cells whose color changed as they aged, which are drawn along with the next frame
+    aged: Vec<(usize, usize)>,
+    width: usize,
+    height: usize,
+    camera: Camera,
+    /// The size of the terminal, where the bottom row is kept for the status line
+    screen: (u16, u16),
+    on_resize: OnResize,
     has_rendered: bool,
 }
 
 struct CellColors {
     alive_color: Color,
+    dying_color: Color,
     dead_color: Color,
+    /// The color alive cells fade towards as they age, when cells are colored by their age
+    old_color: Option<Color>,
+    /// The colors of alive cells of each species, which is empty unless the rule has more than one species
+    species_colors: Vec<Color>,
+    /// How many generations alive cells take to fade to the old color
+    max_age: u16,
+    /// The number of states of the rule, used to fade dying cells towards the dead color
+    states: u8,
 }
 
 impl CellColors {
-    fn get_color(&self, state: CellState) -> Color {
+    fn get_color(&self, cell: DrawnCell) -> Color {
         let Self {
             alive_color,
+            dying_color,
             dead_color,
+            old_color,
+            species_colors,
+            max_age,
+            states,
         } = self;
 
-        if state == CellState::Alive {
-            *alive_color
-        } else {
-            *dead_color
+        match cell.state {
+            CellState::Alive => {
+                let alive_color = if species_colors.is_empty() {
+                    *alive_color
+                } else {
+                    species_colors[usize::from(cell.species) % species_colors.len()]
+                };
+
+                match old_color {
+                    Some(old_color) => {
+                        let age = u16::try_from(cell.age.saturating_sub(1)).unwrap_or(u16::MAX);
+                        fade(
+                            alive_color,
+                            *old_color,
+                            f32::from(age.min(*max_age)) / f32::from(*max_age),
+                        )
+                    }
+                    None => alive_color,
+                }
+            }
+            CellState::Dead => *dead_color,
+            CellState::Dying(dying_stage) => {
+                let amount = f32::from(dying_stage - 1) / f32::from(states - 1);
+                fade(*dying_color, *dead_color, amount)
+            }
         }
     }
 }
 
+/// Mixes two colors, which is only possible when both are RGB colors, so otherwise the first color is kept.
+fn fade(from: Color, to: Color, amount: f32) -> Color {
+    match (from, to) {
+        (
+            Color::Rgb {
+                r: from_r,
+                g: from_g,
+                b: from_b,
+            },
+            Color::Rgb {
+                r: to_r,
+                g: to_g,
+                b: to_b,
+            },
+        ) => {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let mix = |from: u8, to: u8| {
+                (f32::from(from) * (1.0 - amount) + f32::from(to) * amount).round() as u8
+            };
+
+            Color::Rgb {
+                r: mix(from_r, to_r),
+                g: mix(from_g, to_g),
+                b: mix(from_b, to_b),
+            }
+        }
+        _ => from,
+    }
+}
+
 impl Backend {
-    fn new(alive_color: Color, dead_color: Color) -> crossterm::Result<Self> {
+    fn new(
+        colors: CellColors,
+        mode: RenderMode,
+        on_resize: OnResize,
+        state: &State,
+    ) -> crossterm::Result<Self> {
+        let (width, height) = (state.width(), state.height());
+        let ages = colors.old_color.map(|_| Ages::new(state));
+        let tiling = mode.tiling(state.neighbourhood().tiling());
+
         enable_raw_mode()?;
         let mut terminal = stdout();
         execute!(
@@ -48,15 +161,339 @@ impl Backend {
             cursor::Hide,
         )?;
 
+        let screen = crossterm::terminal::size()?;
+        let camera = Camera::new(
+            mode.cell_size(),
+            board_screen(tiling, screen),
+            (width, height),
+        );
+
         Ok(Self {
             terminal,
+            colors,
+            mode,
+            tiling,
+            cells: vec![CellState::Dead; width * height],
+            species: vec![0; width * height],
+            ages,
+            aged: Vec::new(),
+            width,
+            height,
+            camera,
+            screen,
+            on_resize,
             has_rendered: false,
-            colors: CellColors {
-                alive_color,
-                dead_color,
-            },
         })
     }
+
+    /// Fits the board to a new size of the terminal, and draws it again.
+    ///
+    /// The board is resized to fill the terminal when the backend was configured to do so, and otherwise keeps its
+    /// size, with the view moved to stay within it.
+    ///
+    /// # Errors
+    /// When it fails to draw to the terminal, it will error.
+    pub fn resize(&mut self, columns: u16, rows: u16, state: &mut State) -> crossterm::Result<()> {
+        self.screen = (columns, rows);
+
+        if self.on_resize == OnResize::Fit {
+            let (width, height) = fitted_size(self.mode, self.tiling, self.screen);
+            state.resize(width, height);
+
+            self.width = width;
+            self.height = height;
+            self.cells = state.cells();
+            self.species = (0..width * height)
+                .map(|index| state.species_at(index))
+                .collect();
+            self.aged.clear();
+        }
+
+        self.camera.resize(
+            board_screen(self.tiling, self.screen),
+            (self.width, self.height),
+        );
+        self.redraw()
+    }
+
+    /// Finds the index of the cell drawn at a position in the terminal, such as where the mouse was clicked.
+    ///
+    /// When a character draws several cells, this is the top left of them.
+    pub fn cell_at(&self, column: u16, row: u16) -> Option<usize> {
+        let (_, rows) = board_screen(self.tiling, self.screen);
+        let column = board_column(self.tiling, rows, usize::from(row), usize::from(column))?;
+        let (x, y) = self.camera.cell_at(column, usize::from(row))?;
+        Some(y * self.width + x)
+    }
+
+    /// Updates the ages of the cells once the state has changed, when alive cells are colored by their age.
+    ///
+    /// The cells whose color changed are drawn along with the next frame, so this should be called before rendering.
+    pub fn update_ages(&mut self, state: &State) {
+        let Some(ages) = &mut self.ages else {
+            return;
+        };
+
+        // Cells older than the max age have already faded to the old color
+        let max_age = u32::from(self.colors.max_age);
+        let changed = ages.update(state);
+        self.aged.extend(
+            changed
+                .into_iter()
+                .filter(|&index| ages.get(index) <= max_age + 1)
+                .filter_map(|index| {
+                    self.camera
+                        .character_of(index % self.width, index / self.width)
+                }),
+        );
+    }
+
+    /// Moves the view of the board by some characters across and down.
+    ///
+    /// # Errors
+    /// When it fails to redraw the board, it will error.
+    pub fn pan(&mut self, columns: isize, rows: isize) -> crossterm::Result<()> {
+        self.camera.pan(columns, rows);
+        self.redraw()
+    }
+
+    /// Zooms the view of the board in, so each cell drawn stands for fewer cells.
+    ///
+    /// # Errors
+    /// When it fails to redraw the board, it will error.
+    pub fn zoom_in(&mut self) -> crossterm::Result<()> {
+        if self.camera.zoom_in() {
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    /// Zooms the view of the board out, so each cell drawn stands for a square of cells.
+    ///
+    /// # Errors
+    /// When it fails to redraw the board, it will error.
+    pub fn zoom_out(&mut self) -> crossterm::Result<()> {
+        if self.camera.zoom_out() {
+            self.redraw()?;
+        }
+        Ok(())
+    }
+
+    /// Draws the status line at the bottom of the terminal, with the generation, population and status of the board
+    /// and the cells which are shown.
+    ///
+    /// # Errors
+    /// When it fails to draw to the terminal, it will error.
+    pub fn render_status(&mut self, runner: &Runner) -> crossterm::Result<()> {
+        let state = runner.state();
+        let (columns, rows) = self.camera.shown();
+        let Coordinates { x, y } = state.viewport();
+
+        #[allow(clippy::cast_possible_wrap)]
+        let mut status = format!(
+            "Generation {}, {} alive, {} | x {} to {}, y {} to {}, zoom 1:{}",
+            state.generation(),
+            state.population(),
+            state.status(),
+            x + columns.start as i64,
+            x + columns.end as i64 - 1,
+            y + rows.start as i64,
+            y + rows.end as i64 - 1,
+            self.camera.zoom(),
+        );
+        if runner.is_paused() {
+            status.push_str(" | paused");
+        }
+
+        // The previous status is overwritten, and anything which does not fit is cut off
+        let (width, height) = self.screen;
+        let status = format!("{status:<width$.width$}", width = usize::from(width));
+
+        queue!(
+            self.terminal,
+            MoveTo(0, height.saturating_sub(1)),
+            ResetColor,
+            Print(status),
+        )?;
+        self.terminal.flush()
+    }
+
+    /// Draws the pattern picker over the top left of the board, with the selected pattern highlighted.
+    ///
+    /// # Errors
+    /// When it fails to draw to the terminal, it will error.
+    pub fn render_picker(&mut self, picker: &Picker) -> crossterm::Result<()> {
+        let title = "Place a pattern (enter or click to place, escape to close)";
+        let lines = std::iter::once(title)
+            .chain(library::LIBRARY.iter().map(|pattern| pattern.name))
+            .collect::<Vec<_>>();
+
+        let (columns, _) = self.screen;
+        let width = lines
+            .iter()
+            .map(|line| line.len() + 2)
+            .max()
+            .unwrap_or(0)
+            .min(usize::from(columns));
+
+        for (row, line) in lines.into_iter().enumerate() {
+            let attribute = if row == picker.selected() + 1 {
+                Attribute::Reverse
+            } else {
+                Attribute::Reset
+            };
+
+            #[allow(clippy::cast_possible_truncation)]
+            queue!(
+                self.terminal,
+                MoveTo(0, row as u16),
+                ResetColor,
+                SetAttribute(attribute),
+                Print(format!(
+                    " {line:<width$.width$}",
+                    width = width.saturating_sub(1)
+                )),
+                SetAttribute(Attribute::Reset),
+            )?;
+        }
+
+        self.terminal.flush()
+    }
+
+    /// Draws every cell which is shown, such as once the view has moved or the pattern picker has closed.
+    ///
+    /// # Errors
+    /// When it fails to draw to the terminal, it will error.
+    pub fn redraw(&mut self) -> crossterm::Result<()> {
+        if !self.has_rendered {
+            return Ok(());
+        }
+
+        queue!(self.terminal, ResetColor, Clear(ClearType::All))?;
+        let characters = self.camera.characters().collect();
+        self.draw(characters)
+    }
+
+    /// Draws some characters, given by their rows and columns in order.
+    fn draw(&mut self, characters: Vec<(usize, usize)>) -> crossterm::Result<()> {
+        let Self {
+            terminal,
+            colors,
+            mode,
+            tiling,
+            cells,
+            species,
+            ages,
+            width,
+            camera,
+            screen,
+            ..
+        } = self;
+
+        let (cells, species, ages, width) = (&*cells, &*species, ages.as_ref(), *width);
+        let (_, rows) = board_screen(*tiling, *screen);
+        let zoom = camera.zoom();
+        let mut previous_row = None;
+
+        for character in characters {
+            // When zoomed out, each cell drawn is the most alive of the cells it stands for
+            let glyph = mode.glyph(|x, y| {
+                let (columns, rows) = camera.cells_of(character, (x, y));
+                most_alive(rows.flat_map(|y| {
+                    columns.clone().map(move |x| {
+                        let index = y * width + x;
+                        DrawnCell {
+                            state: cells[index],
+                            age: ages.map_or(0, |ages| ages.get(index)),
+                            species: species[index],
+                        }
+                    })
+                }))
+            });
+
+            let (glyph, repeat) = match tiling {
+                Tiling::Square => (glyph, 1),
+                // Each hexagon is two characters wide, so each row can be half a hexagon to the left of the row above
+                Tiling::Hexagonal => (glyph, 2),
+                Tiling::Triangular => {
+                    let (columns, rows) = camera.cells_of(character, (0, 0));
+                    let points_up = (columns.start / zoom + rows.start / zoom) % 2 == 0;
+                    (triangle(glyph.background, points_up), 1)
+                }
+            };
+
+            #[allow(clippy::cast_possible_truncation)]
+            let [row, column] = [character.0, screen_column(*tiling, rows, character)]
+                .map(|position| position as u16);
+
+            if previous_row != Some(row) {
+                queue!(terminal, MoveToRow(row))?;
+                previous_row = Some(row);
+            }
+
+            queue!(
+                terminal,
+                MoveToColumn(column),
+                SetForegroundColor(colors.get_color(glyph.foreground)),
+                SetBackgroundColor(colors.get_color(glyph.background)),
+                Print(glyph.character.to_string().repeat(repeat)),
+            )?;
+        }
+
+        terminal.flush()
+    }
+}
+
+/// Gets the area of the terminal the board is drawn in, as characters across and down, which leaves the bottom row
+/// for the status line.
+///
+/// Hexagons are two characters wide, and each row is drawn one character to the left of the row above, so fewer fit
+/// across.
+fn board_screen(tiling: Tiling, (columns, rows): (u16, u16)) -> (usize, usize) {
+    let (columns, rows) = (usize::from(columns), usize::from(rows.saturating_sub(1)));
+
+    match tiling {
+        Tiling::Hexagonal => (columns.saturating_sub(rows.saturating_sub(1)) / 2, rows),
+        Tiling::Square | Tiling::Triangular => (columns, rows),
+    }
+}
+
+/// Finds the column of the terminal a character is drawn at, given its row and column in the area the board is drawn
+/// in, which has `rows` rows.
+fn screen_column(tiling: Tiling, rows: usize, (row, column): (usize, usize)) -> usize {
+    match tiling {
+        Tiling::Hexagonal => 2 * column + rows.saturating_sub(row + 1),
+        Tiling::Square | Tiling::Triangular => column,
+    }
+}
+
+/// Finds the column of the character drawn at a column of the terminal, which is `None` when nothing is drawn there.
+fn board_column(tiling: Tiling, rows: usize, row: usize, column: usize) -> Option<usize> {
+    match tiling {
+        Tiling::Hexagonal => Some(column.checked_sub(rows.checked_sub(row + 1)?)? / 2),
+        Tiling::Square | Tiling::Triangular => Some(column),
+    }
+}
+
+/// Gets the size of the board which fills the terminal, which holds more cells when each character draws several.
+pub(crate) fn fitted_size(mode: RenderMode, tiling: Tiling, screen: (u16, u16)) -> (usize, usize) {
+    let (columns, rows) = board_screen(mode.tiling(tiling), screen);
+    let (cell_width, cell_height) = mode.cell_size();
+    (columns * cell_width, rows * cell_height)
+}
+
+#[test]
+fn hexagons_are_drawn_two_characters_wide_and_skewed() {
+    let screen = (20, 6);
+    let (columns, rows) = board_screen(Tiling::Hexagonal, screen);
+    assert_eq!((columns, rows), (8, 5));
+
+    // The bottom row starts at the left of the terminal, and each row above is one character further right
+    assert_eq!(screen_column(Tiling::Hexagonal, rows, (4, 0)), 0);
+    assert_eq!(screen_column(Tiling::Hexagonal, rows, (0, 7)), 18);
+    assert_eq!(board_column(Tiling::Hexagonal, rows, 0, 19), Some(7));
+    assert_eq!(board_column(Tiling::Hexagonal, rows, 1, 2), None);
+    assert_eq!(board_column(Tiling::Square, rows, 1, 2), Some(2));
 }
 
 impl RendererBackend<crossterm::ErrorKind> for Backend {
@@ -64,79 +501,107 @@ impl RendererBackend<crossterm::ErrorKind> for Backend {
 
     fn render(&mut self, state: state::Frame) -> crossterm::Result<()> {
         let Self {
-            terminal,
-            colors,
+            cells,
+            species: cell_species,
+            width,
+            height,
+            camera,
+            aged,
             has_rendered,
+            ..
         } = self;
 
-        let mut previous_y = -10_i32;
+        // The characters which draw a changed cell, by row and then column
+        let mut changed = std::mem::take(aged);
 
         for CellRenderInfo {
             coordinates: Coordinates { x, y },
             state,
+            species,
             needs_rerender,
         } in state
         {
             if needs_rerender || !*has_rendered {
-                let color = colors.get_color(state);
-
-                if previous_y != y {
-                    queue!(
-                        terminal,
-                        MoveToRow(
-                            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-                            {
-                                y as _
-                            }
-                        )
-                    )?;
-                    previous_y = y;
+                let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) else {
+                    continue;
+                };
+                if x >= *width || y >= *height {
+                    continue;
                 }
 
-                #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-                {
-                    queue!(
-                        terminal,
-                        MoveToColumn(
-                            #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-                            {
-                                x as _
-                            }
-                        ),
-                        SetBackgroundColor(color),
-                        Print(" "),
-                    )?;
-                }
+                cells[y * *width + x] = state;
+                cell_species[y * *width + x] = species;
+                changed.extend(camera.character_of(x, y));
             }
         }
 
+        changed.sort_unstable();
+        changed.dedup();
+
         *has_rendered = true;
 
-        terminal.flush()?;
+        self.draw(changed)
+    }
 
-        Ok(())
+    fn frame_kind(&self) -> FrameKind {
+        // Once the whole board has been drawn, only the cells that change need to be redrawn
+        if self.has_rendered {
+            FrameKind::Diff
+        } else {
+            FrameKind::Full
+        }
     }
 
-    fn renderer(
-        Config {
-            rows,
-            columns,
+    fn renderer(config: Config) -> crossterm::Result<Renderer<Self, crossterm::ErrorKind>> {
+        let state = config.state()?;
+
+        let Config {
             alive_color,
+            dying_color,
             dead_color,
-        }: Config,
-    ) -> crossterm::Result<Renderer<Self, crossterm::ErrorKind>> {
-        let (columns, rows) = if let (Some(columns), Some(rows)) = (columns, rows) {
-            (columns, rows)
-        } else {
-            let (terminal_columns, terminal_rows) = crossterm::terminal::size()?;
-            (
-                columns.unwrap_or(terminal_columns as _),
-                rows.unwrap_or(terminal_rows as _),
-            )
+            old_color,
+            species_colors,
+            max_age,
+            theme_file,
+            theme,
+            render_mode,
+            on_resize,
+            ..
+        } = config;
+
+        // Colors given on the command line take precedence over the theme
+        let theme = match (theme_file, theme) {
+            (Some(path), Some(name)) => Theme::load(&path, &name)?,
+            _ => Theme::default(),
+        };
+        let color = |color: Option<ColorWrapper>, themed: Option<ColorWrapper>, default| {
+            color.or(themed).map_or(default, ColorWrapper::into_color)
         };
 
-        let state = State::new(columns, rows);
-        let backend = Self::new(alive_color.into_color(), dead_color.into_color())?;
+        let backend = Self::new(
+            CellColors {
+                alive_color: color(alive_color, theme.alive, Color::White),
+                dying_color: color(dying_color, theme.dying, Color::Red),
+                dead_color: color(dead_color, theme.dead, Color::Black),
+                old_color: old_color.or(theme.old).map(ColorWrapper::into_color),
+                species_colors: if state.rule().species() > 1 {
+                    Some(species_colors)
+                        .filter(|colors| !colors.is_empty())
+                        .or(theme.species)
+                        .map_or_else(
+                            || vec![Color::Red, Color::Blue, Color::Green, Color::Yellow],
+                            |colors| colors.into_iter().map(ColorWrapper::into_color).collect(),
+                        )
+                } else {
+                    Vec::new()
+                },
+                max_age: max_age.or(theme.max_age.map(NonZeroU16::get)).unwrap_or(16),
+                states: state.rule().states(),
+            },
+            render_mode,
+            on_resize,
+            &state,
+        )?;
 
         Ok(Renderer::new(state, backend))
     }
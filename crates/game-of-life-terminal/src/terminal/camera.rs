@@ -0,0 +1,249 @@
+use std::ops::Range;
+
+/// The part of the board which is shown in the terminal, which can be panned around the board and zoomed out so
+/// each cell drawn stands for a square of cells.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Camera {
+    /// The column of the top left cell shown
+    x: usize,
+    /// The row of the top left cell shown
+    y: usize,
+    /// How many cells across and down each cell drawn stands for, which is always a power of two
+    zoom: usize,
+    /// How many cells each character draws, across and down
+    cell_size: (usize, usize),
+    /// The size of the area the board is drawn in (in characters)
+    screen: (usize, usize),
+    /// The size of the board (in cells)
+    board: (usize, usize),
+}
+
+impl Camera {
+    /// Creates a camera showing the top left of the board, with one cell drawn for each cell of the board.
+    pub fn new(cell_size: (usize, usize), screen: (usize, usize), board: (usize, usize)) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            zoom: 1,
+            cell_size,
+            screen,
+            board,
+        }
+    }
+
+    /// Changes the size of the screen or the board, keeping the camera in place as far as possible.
+    pub fn resize(&mut self, screen: (usize, usize), board: (usize, usize)) {
+        self.screen = screen;
+        self.board = board;
+        self.clamp();
+    }
+
+    pub fn zoom(&self) -> usize {
+        self.zoom
+    }
+
+    /// Gets how many cells of the board each character stands for, across and down.
+    fn character_size(&self) -> (usize, usize) {
+        let (cell_width, cell_height) = self.cell_size;
+        (cell_width * self.zoom, cell_height * self.zoom)
+    }
+
+    /// Gets the columns and rows of the cells which are shown, which may be fewer than fit on the screen when the
+    /// board is small.
+    pub fn shown(&self) -> (Range<usize>, Range<usize>) {
+        let (character_width, character_height) = self.character_size();
+        let (columns, rows) = self.screen;
+        let (width, height) = self.board;
+
+        (
+            self.x..width.min(self.x + columns * character_width),
+            self.y..height.min(self.y + rows * character_height),
+        )
+    }
+
+    /// Moves the camera by some characters across and down, stopping at the edges of the board.
+    pub fn pan(&mut self, columns: isize, rows: isize) {
+        let (character_width, character_height) = self.character_size();
+
+        #[allow(clippy::cast_possible_wrap)]
+        let (character_width, character_height) =
+            (character_width as isize, character_height as isize);
+
+        self.x = self.x.saturating_add_signed(columns * character_width);
+        self.y = self.y.saturating_add_signed(rows * character_height);
+        self.clamp();
+    }
+
+    /// Halves how many cells each cell drawn stands for, keeping the middle of the view in place.
+    ///
+    /// Returns whether the camera could zoom in, which it can't once each cell drawn is one cell.
+    pub fn zoom_in(&mut self) -> bool {
+        if self.zoom == 1 {
+            return false;
+        }
+
+        self.set_zoom(self.zoom / 2);
+        true
+    }
+
+    /// Doubles how many cells each cell drawn stands for, keeping the middle of the view in place.
+    ///
+    /// Returns whether the camera could zoom out, which it can't once the whole board is shown.
+    pub fn zoom_out(&mut self) -> bool {
+        let (columns, rows) = self.shown();
+        if columns.len() >= self.board.0 && rows.len() >= self.board.1 {
+            return false;
+        }
+
+        self.set_zoom(self.zoom * 2);
+        true
+    }
+
+    fn set_zoom(&mut self, zoom: usize) {
+        let (columns, rows) = self.shown();
+        let middle = (
+            columns.start + columns.len() / 2,
+            rows.start + rows.len() / 2,
+        );
+
+        self.zoom = zoom;
+
+        let (character_width, character_height) = self.character_size();
+        let (screen_columns, screen_rows) = self.screen;
+        self.x = middle
+            .0
+            .saturating_sub(screen_columns * character_width / 2);
+        self.y = middle.1.saturating_sub(screen_rows * character_height / 2);
+        self.clamp();
+    }
+
+    /// Keeps as much of the board in view as possible, so the camera never shows beyond the bottom right of the
+    /// board unless the whole board fits.
+    fn clamp(&mut self) {
+        let (character_width, character_height) = self.character_size();
+        let (columns, rows) = self.screen;
+        let (width, height) = self.board;
+
+        self.x = self.x.min(width.saturating_sub(columns * character_width));
+        self.y = self.y.min(height.saturating_sub(rows * character_height));
+    }
+
+    /// Finds the character which draws a cell, as its row and column, when the cell is shown.
+    pub fn character_of(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let (columns, rows) = self.shown();
+        let (character_width, character_height) = self.character_size();
+
+        (columns.contains(&x) && rows.contains(&y)).then(|| {
+            (
+                (y - self.y) / character_height,
+                (x - self.x) / character_width,
+            )
+        })
+    }
+
+    /// Gets every character which draws part of the board, as its row and column.
+    pub fn characters(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (columns, rows) = self.shown();
+        let (character_width, character_height) = self.character_size();
+
+        let columns = columns.len().div_ceil(character_width);
+        let rows = rows.len().div_ceil(character_height);
+        (0..rows).flat_map(move |row| (0..columns).map(move |column| (row, column)))
+    }
+
+    /// Gets the cells which one cell drawn in a character stands for, given the row and column of the character and
+    /// the position of the cell drawn within it.
+    pub fn cells_of(
+        &self,
+        (row, column): (usize, usize),
+        (x, y): (usize, usize),
+    ) -> (Range<usize>, Range<usize>) {
+        let (cell_width, cell_height) = self.cell_size;
+        let (width, height) = self.board;
+
+        let left = self.x + (column * cell_width + x) * self.zoom;
+        let top = self.y + (row * cell_height + y) * self.zoom;
+
+        (
+            left.min(width)..(left + self.zoom).min(width),
+            top.min(height)..(top + self.zoom).min(height),
+        )
+    }
+
+    /// Finds the top left cell drawn at a position in the terminal, as its column and row on the board.
+    pub fn cell_at(&self, column: usize, row: usize) -> Option<(usize, usize)> {
+        let (columns, rows) = self.shown();
+        let (character_width, character_height) = self.character_size();
+
+        let x = self.x + column * character_width;
+        let y = self.y + row * character_height;
+
+        (columns.contains(&x) && rows.contains(&y)).then_some((x, y))
+    }
+}
+
+#[test]
+fn cameras_pan_within_the_board() {
+    let mut camera = Camera::new((1, 2), (10, 5), (40, 40));
+    assert_eq!(camera.shown(), (0..10, 0..10));
+
+    camera.pan(3, 2);
+    assert_eq!(camera.shown(), (3..13, 4..14));
+
+    camera.pan(100, 100);
+    assert_eq!(camera.shown(), (30..40, 30..40));
+
+    camera.pan(-100, -1);
+    assert_eq!(camera.shown(), (0..10, 28..38));
+}
+
+#[test]
+fn cameras_zoom_around_the_middle() {
+    let mut camera = Camera::new((1, 1), (10, 10), (100, 100));
+    camera.pan(20, 20);
+    assert_eq!(camera.shown(), (20..30, 20..30));
+
+    assert!(camera.zoom_out());
+    assert_eq!(camera.zoom(), 2);
+    assert_eq!(camera.shown(), (15..35, 15..35));
+    assert_eq!(camera.cells_of((1, 2), (0, 0)), (19..21, 17..19));
+
+    assert!(camera.zoom_in());
+    assert!(!camera.zoom_in());
+    assert_eq!(camera.shown(), (20..30, 20..30));
+}
+
+#[test]
+fn cameras_stop_zooming_out_once_the_board_fits() {
+    let mut camera = Camera::new((2, 4), (10, 10), (50, 50));
+
+    assert!(camera.zoom_out());
+    assert!(camera.zoom_out());
+    assert!(!camera.zoom_out());
+    assert_eq!(camera.zoom(), 4);
+    assert_eq!(camera.shown(), (0..50, 0..50));
+    assert_eq!(camera.characters().count(), 7 * 4);
+}
+
+#[test]
+fn cells_are_found_on_screen() {
+    let mut camera = Camera::new((1, 2), (10, 5), (40, 40));
+    camera.pan(1, 1);
+
+    assert_eq!(camera.character_of(5, 7), Some((2, 4)));
+    assert_eq!(camera.character_of(0, 7), None);
+    assert_eq!(camera.cell_at(4, 2), Some((5, 6)));
+    assert_eq!(camera.cell_at(10, 2), None);
+}
+
+#[test]
+fn cameras_stay_within_resized_boards() {
+    let mut camera = Camera::new((1, 1), (10, 10), (40, 40));
+    camera.pan(20, 20);
+
+    camera.resize((20, 10), (40, 40));
+    assert_eq!(camera.shown(), (20..40, 20..30));
+
+    camera.resize((20, 10), (30, 25));
+    assert_eq!(camera.shown(), (10..30, 15..25));
+}
@@ -1,5 +1,14 @@
-use clap::Args;
+use super::{fitted_size, RenderMode};
+use clap::{Args, ValueEnum};
 use crossterm::style::Color;
+use game_of_life_core::{
+    boundary::Boundary,
+    hashlife::HashLife,
+    neighbourhood::{parse_neighbourhood, Neighbourhood, Tiling},
+    pattern::Pattern,
+    rule::Rule,
+    state::{State, Storage},
+};
 use nom::{
     branch::alt,
     character::complete::{digit1, satisfy},
@@ -9,7 +18,8 @@ use nom::{
 use nom_supreme::{
     error::ErrorTree, final_parser::final_parser, parser_ext::ParserExt, tag::complete::tag,
 };
-use std::str::FromStr;
+use serde::Deserialize;
+use std::{fs, io, path::PathBuf, str::FromStr, sync::Arc};
 
 #[derive(Args)]
 pub struct Config {
@@ -19,15 +29,195 @@ pub struct Config {
     /// The width of the board (in columns)
     #[clap(short = 'c', long)]
     pub columns: Option<usize>,
-    /// The color of an alive cell as a color (in the form ANSI-[n], #[r][g][b], or a named color)
-    #[clap(long, value_parser = parse_color, default_value = "white")]
-    pub alive_color: ColorWrapper,
-    /// The color of an alive cell as a color (in the form ANSI-[n], #[r][g][b], or a named color)
-    #[clap(long, value_parser = parse_color, default_value = "black")]
-    pub dead_color: ColorWrapper,
+    /// The color of an alive cell as a color (in the form ANSI-[n], #[r][g][b], or a named color), which is white by
+    /// default
+    #[clap(long, value_parser = parse_color)]
+    pub alive_color: Option<ColorWrapper>,
+    /// The color of an alive cell as a color (in the form ANSI-[n], #[r][g][b], or a named color), which is black by
+    /// default
+    #[clap(long, value_parser = parse_color)]
+    pub dead_color: Option<ColorWrapper>,
+    /// The color of a cell which has just started dying, under a rule with more than two states (such as B2/S/C3),
+    /// which is red by default
+    ///
+    /// Later stages of dying fade towards the dead color when both are hex colors.
+    #[clap(long, value_parser = parse_color)]
+    pub dying_color: Option<ColorWrapper>,
+    /// The color alive cells fade towards as they age, which colors cells by how many generations they have been alive
+    ///
+    /// Cells only fade when both the alive and old colors are hex colors.
+    #[clap(long, value_parser = parse_color)]
+    pub old_color: Option<ColorWrapper>,
+    /// The colors of alive cells of each species, under a rule with more than one species (such as Immigration or
+    /// `QuadLife`), separated by commas, which are red, blue, green and yellow by default
+    ///
+    /// When there are fewer colors than species, the colors are repeated.
+    #[clap(long, value_parser = parse_color, value_delimiter = ',')]
+    pub species_colors: Vec<ColorWrapper>,
+    /// How many generations alive cells take to fade to the old color, which is 16 by default
+    #[clap(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub max_age: Option<u16>,
+    /// A TOML file of named color themes, where each theme is a table which can set the alive, dead, dying and old
+    /// colors, the list of species colors and the max-age
+    #[clap(long, requires = "theme")]
+    pub theme_file: Option<PathBuf>,
+    /// The theme to use from the theme file, whose colors are used unless they are given on the command line
+    #[clap(long, requires = "theme_file")]
+    pub theme: Option<String>,
+    /// The rule used to update the board, as a rulestring in B/S notation (such as B36/S23, or B2/S/C3 with dying states)
+    /// or the name of a rule with several species (Immigration or `QuadLife`)
+    ///
+    /// Defaults to the rule of the pattern, or B3/S23 when there is no pattern.
+    #[clap(long)]
+    pub rule: Option<Rule>,
+    /// How cells beyond the edges of the board are treated (torus, dead, mirror or klein)
+    #[clap(long, default_value_t = Boundary::default())]
+    pub boundary: Boundary,
+    /// Simulate an unbounded plane, which the board is a window onto, so patterns can grow beyond its edges
+    ///
+    /// The boundary is ignored, and the board can't be rewound.
+    #[clap(long)]
+    pub unbounded: bool,
+    /// Which cells are the neighbours of each cell (moore, von-neumann, hex or triangular), where moore and
+    /// von-neumann can have a larger radius such as moore:2
+    #[clap(long, value_parser = parse_neighbourhood)]
+    pub neighbourhood: Option<Arc<dyn Neighbourhood>>,
+    /// The seed used to randomly fill the board, so that a run can be reproduced (random by default)
+    #[clap(long)]
+    pub seed: Option<u64>,
+    /// The proportion of cells that are alive at the start, between 0 and 1
+    #[clap(long, value_parser = parse_density, default_value_t = 0.5)]
+    pub density: f64,
+    /// A pattern file to place in the middle of an empty board (in RLE or plaintext format)
+    #[clap(long)]
+    pub pattern: Option<PathBuf>,
+    /// A snapshot saved with --save to resume from, which sets the size, rule, boundary, neighbourhood and generation
+    /// of the board
+    #[clap(long, conflicts_with_all = ["rows", "columns", "rule", "boundary", "neighbourhood", "seed", "pattern"])]
+    pub snapshot: Option<PathBuf>,
+    /// Jump ahead by this many generations before the first frame, using the hashlife algorithm
+    ///
+    /// Hashlife simulates an unbounded plane, so the boundary is ignored while skipping. Cells which leave the board
+    /// while skipping are dropped, unless the board is unbounded.
+    #[clap(long, default_value_t = 0)]
+    pub skip: u64,
+    /// The most memory used to remember earlier generations, so the board can be rewound (in MiB, or 0 to disable
+    /// rewinding)
+    #[clap(long, default_value_t = 16)]
+    pub history: usize,
+    /// How cells are drawn, where half blocks fit twice as many cells in the terminal and braille fits eight times as
+    /// many, when the size of the board is not given
+    ///
+    /// Hexagonal and triangular cells are only drawn in their shape one cell per character, and are drawn as squares
+    /// otherwise.
+    #[clap(long, value_enum, default_value_t = RenderMode::default())]
+    pub render_mode: RenderMode,
+    /// What happens to the board when the terminal is resized
+    #[clap(long, value_enum, default_value_t = OnResize::default())]
+    pub on_resize: OnResize,
+}
+
+impl Config {
+    /// Creates the board described by the config, without touching the terminal other than to find its size when
+    /// the size of the board is not given.
+    ///
+    /// # Errors
+    /// When a pattern or snapshot can't be read or parsed, or the rule can't be skipped ahead with hashlife, it will
+    /// error.
+    pub fn state(&self) -> io::Result<State> {
+        let Self {
+            rows,
+            columns,
+            rule,
+            boundary,
+            unbounded,
+            neighbourhood,
+            seed,
+            density,
+            pattern,
+            snapshot,
+            skip,
+            history,
+            render_mode,
+            ..
+        } = self;
+
+        // By default the board fills the terminal
+        let (columns, rows) = if let (Some(columns), Some(rows)) = (*columns, *rows) {
+            (columns, rows)
+        } else {
+            let tiling = neighbourhood
+                .as_ref()
+                .map_or(Tiling::Square, |neighbourhood| neighbourhood.tiling());
+            let (fitted_columns, fitted_rows) =
+                fitted_size(*render_mode, tiling, crossterm::terminal::size()?);
+            (
+                columns.unwrap_or(fitted_columns),
+                rows.unwrap_or(fitted_rows),
+            )
+        };
+
+        let state = if let Some(path) = snapshot {
+            State::from_snapshot(&fs::read(path)?)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+        } else {
+            let state = if let Some(path) = pattern {
+                let pattern = Pattern::parse(&fs::read_to_string(path)?)
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                let mut state = State::empty(columns, rows)
+                    .with_rule(rule.or(pattern.rule).unwrap_or_default());
+                pattern.stamp_centered(&mut state);
+                state
+            } else {
+                let seed = seed.unwrap_or_else(rand::random);
+                State::from_seed(columns, rows, seed, *density)
+                    .with_rule(rule.unwrap_or_default())
+                    .with_random_species(seed)
+            }
+            .with_boundary(*boundary);
+
+            match neighbourhood {
+                Some(neighbourhood) => state.with_neighbourhood(neighbourhood.clone()),
+                None => state,
+            }
+        };
+
+        let state = if *unbounded {
+            state.with_storage(Storage::Unbounded)
+        } else {
+            state
+        };
+
+        // The board is made unbounded before skipping ahead, so cells which leave it while skipping are kept
+        let state = if *skip > 0 {
+            let mut hashlife = HashLife::from_state(&state)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+            hashlife.step_by(*skip);
+            hashlife.to_state()
+        } else {
+            state
+        };
+
+        Ok(if *history > 0 {
+            state.with_history(history << 20)
+        } else {
+            state
+        })
+    }
+}
+
+/// What happens to the board when the terminal is resized.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OnResize {
+    /// Keep the size of the board, and draw it again to fit the terminal
+    #[default]
+    Keep,
+    /// Resize the board to fill the terminal, cropping cells beyond its new edges or adding dead cells
+    Fit,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
 pub struct ColorWrapper {
     color: Color,
 }
@@ -42,6 +232,14 @@ impl ColorWrapper {
     }
 }
 
+impl TryFrom<String> for ColorWrapper {
+    type Error = ErrorTree<String>;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        parse_color(&input)
+    }
+}
+
 pub trait ToStringError {
     fn to_string_error(self) -> ErrorTree<String>;
 }
@@ -82,6 +280,23 @@ fn color_parses() {
     );
 }
 
+fn parse_density(input: &str) -> Result<f64, String> {
+    let density = input.parse::<f64>().map_err(|error| error.to_string())?;
+
+    if (0.0..=1.0).contains(&density) {
+        Ok(density)
+    } else {
+        Err(format!("{density} is not between 0 and 1"))
+    }
+}
+
+#[test]
+fn density_parses() {
+    assert_eq!(parse_density("0.25"), Ok(0.25));
+    parse_density("1.5").unwrap_err();
+    parse_density("a").unwrap_err();
+}
+
 fn ansi_color(input: &str) -> IResult<&str, Color, ErrorTree<&str>> {
     tag("ANSI-")
         .precedes(digit1)
@@ -0,0 +1,100 @@
+use game_of_life_core::pattern::Pattern;
+
+/// A classic pattern which is built into the binary, so it can be placed without a pattern file.
+#[allow(clippy::module_name_repetitions)]
+pub struct LibraryPattern {
+    pub name: &'static str,
+    rle: &'static str,
+}
+
+impl LibraryPattern {
+    /// Parses the pattern, which always succeeds as every pattern in the library is checked by the tests.
+    pub fn pattern(&self) -> Pattern {
+        Pattern::parse_rle(self.rle).expect("library patterns are valid RLE")
+    }
+}
+
+macro_rules! library {
+    ($($name:literal => $file:literal),* $(,)?) => {
+        &[$(LibraryPattern {
+            name: $name,
+            rle: include_str!(concat!("../../patterns/", $file)),
+        }),*]
+    };
+}
+
+/// Every pattern in the library, in the order they are listed in the picker.
+pub const LIBRARY: &[LibraryPattern] = library![
+    "Glider" => "glider.rle",
+    "Lightweight spaceship" => "lwss.rle",
+    "Gosper glider gun" => "gosper-glider-gun.rle",
+    "Pulsar" => "pulsar.rle",
+    "Pentadecathlon" => "pentadecathlon.rle",
+    "Blinker puffer 1" => "blinker-puffer-1.rle",
+    "R-pentomino" => "r-pentomino.rle",
+    "Acorn" => "acorn.rle",
+    "Diehard" => "diehard.rle",
+];
+
+/// A menu of the patterns in the library, which is shown over the board while a pattern is chosen.
+pub struct Picker {
+    selected: usize,
+    /// Whether the board was paused before the picker was opened, as it is paused while the picker is open
+    was_paused: bool,
+}
+
+impl Picker {
+    pub fn new(was_paused: bool) -> Self {
+        Self {
+            selected: 0,
+            was_paused,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn was_paused(&self) -> bool {
+        self.was_paused
+    }
+
+    /// Selects the pattern above, wrapping around to the bottom.
+    pub fn previous(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(LIBRARY.len() - 1);
+    }
+
+    /// Selects the pattern below, wrapping around to the top.
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % LIBRARY.len();
+    }
+
+    pub fn pattern(&self) -> Pattern {
+        LIBRARY[self.selected].pattern()
+    }
+}
+
+#[test]
+fn library_patterns_parse() {
+    for pattern in LIBRARY {
+        let parsed = pattern.pattern();
+        assert_eq!(parsed.name.as_deref(), Some(pattern.name));
+        assert_eq!(parsed.rule, Some(game_of_life_core::rule::Rule::CONWAY));
+    }
+}
+
+#[test]
+fn pickers_wrap_around() {
+    let mut picker = Picker::new(false);
+
+    picker.previous();
+    assert_eq!(picker.selected(), LIBRARY.len() - 1);
+
+    picker.next();
+    picker.next();
+    assert_eq!(picker.selected(), 1);
+    assert_eq!(
+        picker.pattern().name.as_deref(),
+        Some("Lightweight spaceship")
+    );
+}
@@ -0,0 +1,181 @@
+use clap::ValueEnum;
+use game_of_life_core::{neighbourhood::Tiling, prelude::CellState};
+
+/// How cells are drawn with terminal characters.
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[allow(clippy::module_name_repetitions)]
+pub enum RenderMode {
+    /// One cell per character, drawn with the background color
+    #[default]
+    Cell,
+    /// Two cells per character, one above the other, drawn with upper half blocks
+    HalfBlock,
+    /// Eight cells per character, two across and four down, drawn with braille patterns
+    ///
+    /// A character can only have one color, so the dots take the color of the most alive cell.
+    Braille,
+}
+
+/// Gets the character which draws a triangle in the color of a cell, pointing up or down.
+pub fn triangle(cell: DrawnCell, points_up: bool) -> Glyph {
+    Glyph {
+        character: if points_up { '▲' } else { '▼' },
+        foreground: cell,
+        background: CellState::Dead.into(),
+    }
+}
+
+/// A cell to draw, along with how many generations it has been alive for, which is 0 when ages aren't tracked, and
+/// its species.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DrawnCell {
+    pub state: CellState,
+    pub age: u32,
+    pub species: u8,
+}
+
+impl From<CellState> for DrawnCell {
+    fn from(state: CellState) -> Self {
+        Self {
+            state,
+            age: 0,
+            species: 0,
+        }
+    }
+}
+
+/// A character which draws some cells, along with the cells whose colors are used for its foreground and background.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Glyph {
+    pub character: char,
+    pub foreground: DrawnCell,
+    pub background: DrawnCell,
+}
+
+impl RenderMode {
+    /// Gets how many cells each character draws, across and down.
+    pub fn cell_size(self) -> (usize, usize) {
+        match self {
+            Self::Cell => (1, 1),
+            Self::HalfBlock => (1, 2),
+            Self::Braille => (2, 4),
+        }
+    }
+
+    /// Gets the shape the cells of a tiling are drawn in, where only cell mode draws hexagons and triangles, as the
+    /// other modes draw several rows of cells with each character.
+    pub fn tiling(self, tiling: Tiling) -> Tiling {
+        if self == Self::Cell {
+            tiling
+        } else {
+            Tiling::Square
+        }
+    }
+
+    /// Works out the character which draws some cells, given the state of each cell by its position in the character.
+    pub fn glyph(self, cell: impl Fn(usize, usize) -> DrawnCell) -> Glyph {
+        match self {
+            Self::Cell => Glyph {
+                character: ' ',
+                foreground: CellState::Dead.into(),
+                background: cell(0, 0),
+            },
+            Self::HalfBlock => Glyph {
+                character: '▀',
+                foreground: cell(0, 0),
+                background: cell(0, 1),
+            },
+            Self::Braille => {
+                let cells = (0..4).flat_map(|y| (0..2).map(move |x| (x, y)));
+
+                let dots = cells
+                    .clone()
+                    .filter(|&(x, y)| cell(x, y).state != CellState::Dead)
+                    .fold(0, |dots, (x, y)| dots | braille_dot(x, y));
+
+                let foreground = most_alive(cells.map(|(x, y)| cell(x, y)));
+
+                Glyph {
+                    character: char::from_u32(0x2800 + dots).unwrap_or(' '),
+                    foreground,
+                    background: CellState::Dead.into(),
+                }
+            }
+        }
+    }
+}
+
+/// Gets the bit of the braille dot for a cell, where the bottom row was added to braille later than the rest.
+fn braille_dot(x: usize, y: usize) -> u32 {
+    match (x, y) {
+        (0, 3) => 0x40,
+        (1, 3) => 0x80,
+        (x, y) => 1 << (x * 3 + y),
+    }
+}
+
+/// Finds the most alive of some cells, where alive cells come before dying cells, which come before dead cells.
+pub fn most_alive(cells: impl IntoIterator<Item = DrawnCell>) -> DrawnCell {
+    cells
+        .into_iter()
+        .min_by_key(|cell| match cell.state {
+            CellState::Alive => 0,
+            CellState::Dying(dying_stage) => dying_stage,
+            CellState::Dead => u8::MAX,
+        })
+        .unwrap_or_else(|| CellState::Dead.into())
+}
+
+#[test]
+fn braille_glyphs_hold_eight_cells() {
+    let glyph = |alive: &[(usize, usize)]| {
+        RenderMode::Braille.glyph(|x, y| CellState::from(alive.contains(&(x, y))).into())
+    };
+
+    assert_eq!(glyph(&[]).character, '⠀');
+    assert_eq!(glyph(&[(0, 0)]).character, '⠁');
+    assert_eq!(glyph(&[(1, 1), (0, 3)]).character, '⡐');
+
+    let all = (0..4)
+        .flat_map(|y| (0..2).map(move |x| (x, y)))
+        .collect::<Vec<_>>();
+    let glyph = glyph(&all);
+    assert_eq!(glyph.character, '⣿');
+    assert_eq!(glyph.foreground.state, CellState::Alive);
+}
+
+#[test]
+fn braille_dots_take_the_color_of_the_most_alive_cell() {
+    let glyph = RenderMode::Braille.glyph(|x, y| {
+        match (x, y) {
+            (0, 0) => CellState::Dying(3),
+            (1, 2) => CellState::Dying(1),
+            _ => CellState::Dead,
+        }
+        .into()
+    });
+
+    assert_eq!(glyph.character, '⠡');
+    assert_eq!(glyph.foreground.state, CellState::Dying(1));
+}
+
+#[test]
+fn only_cells_are_drawn_in_their_shape() {
+    assert_eq!(
+        RenderMode::Cell.tiling(Tiling::Hexagonal),
+        Tiling::Hexagonal
+    );
+    assert_eq!(
+        RenderMode::HalfBlock.tiling(Tiling::Triangular),
+        Tiling::Square
+    );
+    assert_eq!(triangle(CellState::Alive.into(), false).character, '▼');
+}
+
+#[test]
+fn half_blocks_hold_two_cells() {
+    let glyph = RenderMode::HalfBlock.glyph(|_, y| CellState::from(y == 1).into());
+
+    assert_eq!(glyph.foreground.state, CellState::Dead);
+    assert_eq!(glyph.background.state, CellState::Alive);
+}
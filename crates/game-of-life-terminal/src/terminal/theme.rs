@@ -0,0 +1,91 @@
+use super::config::ColorWrapper;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, io, num::NonZeroU16, path::Path};
+
+/// Colors for drawing the board, where any which are left out are taken from the command line or its defaults.
+///
+/// Themes are loaded by name from a TOML file with a table for each theme, such as
+/// ```toml
+/// [ocean]
+/// alive = "#8fe3ff"
+/// old = "#1050a0"
+/// dead = "#001020"
+/// species = ["#8fe3ff", "#ffb060"]
+/// max-age = 32
+/// ```
+#[derive(Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Theme {
+    pub alive: Option<ColorWrapper>,
+    pub dead: Option<ColorWrapper>,
+    pub dying: Option<ColorWrapper>,
+    pub old: Option<ColorWrapper>,
+    pub species: Option<Vec<ColorWrapper>>,
+    /// How many generations alive cells take to fade to the old color, which can't be 0 as they would never fade
+    pub max_age: Option<NonZeroU16>,
+}
+
+impl Theme {
+    /// Loads a theme by its name from a file of themes.
+    ///
+    /// # Errors
+    /// When the file can't be read or parsed, or has no theme with the name, it will error.
+    pub fn load(path: &Path, name: &str) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?, name)
+    }
+
+    fn parse(input: &str, name: &str) -> io::Result<Self> {
+        let mut themes = toml::from_str::<HashMap<String, Self>>(input)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        themes.remove(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("there is no theme named {name}"),
+            )
+        })
+    }
+}
+
+#[test]
+fn themes_parse() {
+    use crossterm::style::Color;
+
+    let themes = r##"
+        [ocean]
+        alive = "#8fe3ff"
+        dead = "ANSI-17"
+        species = ["red", "ANSI-4"]
+        max-age = 32
+
+        [plain]
+        alive = "white"
+    "##;
+
+    let theme = Theme::parse(themes, "ocean").unwrap();
+    assert_eq!(
+        theme.alive.map(ColorWrapper::into_color),
+        Some(Color::Rgb {
+            r: 0x8f,
+            g: 0xe3,
+            b: 0xff
+        })
+    );
+    assert_eq!(
+        theme.dead.map(ColorWrapper::into_color),
+        Some(Color::AnsiValue(17))
+    );
+    assert_eq!(theme.dying, None);
+    assert_eq!(
+        theme
+            .species
+            .map(|species| species.into_iter().map(ColorWrapper::into_color).collect()),
+        Some(vec![Color::Red, Color::AnsiValue(4)])
+    );
+    assert_eq!(theme.max_age.map(NonZeroU16::get), Some(32));
+
+    Theme::parse(themes, "forest").unwrap_err();
+    Theme::parse("[bad]\nalive = \"not a color\"", "bad").unwrap_err();
+    Theme::parse("[bad]\nglow = \"red\"", "bad").unwrap_err();
+    Theme::parse("[bad]\nmax-age = 0", "bad").unwrap_err();
+}